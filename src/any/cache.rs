@@ -0,0 +1,223 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How the cache is used
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum CacheMode {
+  /// Fresh entries are used, new data is stored
+  Normal,
+  /// Entries are ignored, new data is stored
+  Refresh,
+  /// Only entries are used, expired ones included, nothing is requested
+  Offline,
+}
+
+/// Kinds of cached data, each one in its own directory
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum EntryKind {
+  /// A `TrackInfo`, keyed by song page URL
+  Track,
+  /// Search results, keyed by query
+  Search,
+  /// Cover image bytes, keyed by image URL
+  Cover,
+  /// An `AlbumInfo`, keyed by album page URL
+  Album,
+  /// An `ArtistInfo`, keyed by artist page URL
+  Artist,
+}
+
+impl EntryKind {
+  const ALL: [EntryKind; 5] = [
+    Self::Track,
+    Self::Search,
+    Self::Cover,
+    Self::Album,
+    Self::Artist,
+  ];
+
+  pub(crate) fn name(self) -> &'static str {
+    match self {
+      Self::Track => "track",
+      Self::Search => "search",
+      Self::Cover => "cover",
+      Self::Album => "album",
+      Self::Artist => "artist",
+    }
+  }
+
+  fn dir(self) -> &'static str {
+    match self {
+      Self::Track => "tracks",
+      Self::Search => "searches",
+      Self::Cover => "covers",
+      Self::Album => "albums",
+      Self::Artist => "artists",
+    }
+  }
+}
+
+/// What is stored in every entry file, next to the value
+#[derive(Serialize, Deserialize)]
+pub(crate) struct EntryMeta {
+  pub(crate) kind: EntryKind,
+  pub(crate) key: String,
+  /// Seconds since the unix epoch
+  pub(crate) stored: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry<T> {
+  #[serde(flatten)]
+  meta: EntryMeta,
+  value: T,
+}
+
+/// Files under the cache directory, every entry expires `ttl` after stored
+pub(crate) struct Cache {
+  dir: PathBuf,
+  ttl: Duration,
+  pub(crate) mode: CacheMode,
+}
+
+impl Cache {
+  pub(crate) fn new(dir: PathBuf, ttl: Duration, mode: CacheMode) -> Self {
+    Self { dir, ttl, mode }
+  }
+
+  pub(crate) fn get<T: DeserializeOwned>(&self, kind: EntryKind, key: &str) -> Option<T> {
+    if self.mode == CacheMode::Refresh {
+      return None;
+    }
+    let entry: Entry<T> = serde_json::from_slice(&fs::read(self.path(kind, key, "json")).ok()?).ok()?;
+    (entry.meta.key == key && self.usable(&entry.meta)).then_some(entry.value)
+  }
+
+  pub(crate) fn put<T: Serialize>(&self, kind: EntryKind, key: &str, value: &T) -> Result<(), String> {
+    let entry = Entry {
+      meta: EntryMeta {
+        kind,
+        key: key.into(),
+        stored: now(),
+      },
+      value,
+    };
+    let json = serde_json::to_vec(&entry).map_err(|e| format!("Could not serialize cache entry: {e}"))?;
+    self.write(&self.path(kind, key, "json"), &json)
+  }
+
+  /// Bytes are kept next to the entry file, not inside it
+  pub(crate) fn get_bytes(&self, kind: EntryKind, key: &str) -> Option<Vec<u8>> {
+    self.get::<()>(kind, key)?;
+    fs::read(self.path(kind, key, "bin")).ok()
+  }
+
+  pub(crate) fn put_bytes(&self, kind: EntryKind, key: &str, bytes: &[u8]) -> Result<(), String> {
+    self.write(&self.path(kind, key, "bin"), bytes)?;
+    self.put(kind, key, &())
+  }
+
+  /// Every entry, with whether it expired
+  pub(crate) fn list(&self) -> Vec<(EntryMeta, bool)> {
+    let mut entries: Vec<(EntryMeta, bool)> = self
+      .entry_files()
+      .filter_map(|p| {
+        let entry: Entry<serde::de::IgnoredAny> = serde_json::from_slice(&fs::read(p).ok()?).ok()?;
+        let expired = self.expired(&entry.meta);
+        Some((entry.meta, expired))
+      })
+      .collect();
+    entries.sort_by_key(|(m, _)| std::cmp::Reverse(m.stored));
+    entries
+  }
+
+  /// Remove expired (and unreadable) entries, returns how many were removed
+  pub(crate) fn prune(&self) -> usize {
+    self
+      .entry_files()
+      .filter(|p| {
+        let meta = fs::read(p)
+          .ok()
+          .and_then(|b| serde_json::from_slice::<Entry<serde::de::IgnoredAny>>(&b).ok());
+        meta.is_none_or(|e| self.expired(&e.meta))
+      })
+      .filter(|p| remove_entry(p))
+      .count()
+  }
+
+  /// Remove every entry, returns how many were removed
+  pub(crate) fn clear(&self) -> usize {
+    self.entry_files().filter(|p| remove_entry(p)).count()
+  }
+
+  fn usable(&self, meta: &EntryMeta) -> bool {
+    self.mode == CacheMode::Offline || !self.expired(meta)
+  }
+
+  fn expired(&self, meta: &EntryMeta) -> bool {
+    now().saturating_sub(meta.stored) >= self.ttl.as_secs()
+  }
+
+  fn path(&self, kind: EntryKind, key: &str, ext: &str) -> PathBuf {
+    self
+      .dir
+      .join(kind.dir())
+      .join(format!("{:016x}.{ext}", fnv1a(key)))
+  }
+
+  fn entry_files(&self) -> impl Iterator<Item = PathBuf> + '_ {
+    EntryKind::ALL
+      .iter()
+      .filter_map(|k| fs::read_dir(self.dir.join(k.dir())).ok())
+      .flatten()
+      .flatten()
+      .map(|e| e.path())
+      .filter(|p| p.extension().is_some_and(|e| e == "json"))
+  }
+
+  fn write(&self, path: &Path, bytes: &[u8]) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+      fs::create_dir_all(dir).map_err(|e| format!("Could not create {}: {e}", dir.display()))?;
+    }
+    fs::write(path, bytes).map_err(|e| format!("Could not write {}: {e}", path.display()))
+  }
+}
+
+/// Same page, whatever the URL looks like: no scheme, `www.`, query or
+/// trailing slash
+pub(crate) fn normalize_url(url: &str) -> String {
+  let url = url.split(['?', '#']).next().unwrap_or_default();
+  let url = url.split_once("://").map_or(url, |(_, rest)| rest);
+  let url = url.strip_prefix("www.").unwrap_or(url);
+  url.trim_end_matches('/').to_lowercase()
+}
+
+/// Same search, whatever the case and spacing of the query
+pub(crate) fn normalize_query(query: &str) -> String {
+  query
+    .split_whitespace()
+    .collect::<Vec<_>>()
+    .join(" ")
+    .to_lowercase()
+}
+
+fn remove_entry(path: &Path) -> bool {
+  let _ = fs::remove_file(path.with_extension("bin"));
+  fs::remove_file(path).is_ok()
+}
+
+fn now() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map_or(0, |d| d.as_secs())
+}
+
+/// File names must not change between builds, so no `DefaultHasher`
+fn fnv1a(key: &str) -> u64 {
+  key.bytes().fold(0xcbf29ce484222325, |h, b| {
+    (h ^ b as u64).wrapping_mul(0x100000001b3)
+  })
+}
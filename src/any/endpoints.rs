@@ -0,0 +1,52 @@
+use serde::Deserialize;
+
+/// Base URL of every site the crate talks to, so they can point to a
+/// mirror or a local stand-in server
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub(crate) struct Endpoints {
+  pub(crate) google: String,
+  pub(crate) bing: String,
+  pub(crate) duckduckgo: String,
+  pub(crate) musixmatch: String,
+  pub(crate) genius: String,
+  pub(crate) lrclib: String,
+  /// Spotify public pages, track links are resolved through them
+  pub(crate) spotify: String,
+}
+
+impl Default for Endpoints {
+  fn default() -> Self {
+    Self {
+      google: "https://www.google.com".into(),
+      bing: "https://www.bing.com".into(),
+      duckduckgo: "https://html.duckduckgo.com".into(),
+      musixmatch: "https://www.musixmatch.com".into(),
+      genius: "https://genius.com".into(),
+      lrclib: "https://lrclib.net".into(),
+      spotify: "https://open.spotify.com".into(),
+    }
+  }
+}
+
+impl Endpoints {
+  /// Override each base URL from its `LYRICS_<NAME>_URL` environment variable
+  pub(crate) fn with_env(mut self) -> Self {
+    for (name, url) in [
+      ("GOOGLE", &mut self.google),
+      ("BING", &mut self.bing),
+      ("DUCKDUCKGO", &mut self.duckduckgo),
+      ("MUSIXMATCH", &mut self.musixmatch),
+      ("GENIUS", &mut self.genius),
+      ("LRCLIB", &mut self.lrclib),
+      ("SPOTIFY", &mut self.spotify),
+    ] {
+      if let Ok(v) = std::env::var(format!("LYRICS_{name}_URL")) {
+        *url = v;
+      }
+      // Paths are always appended to the base
+      url.truncate(url.trim_end_matches('/').len());
+    }
+    self
+  }
+}
@@ -0,0 +1,129 @@
+use std::error::Error;
+use std::fmt::{self, Display};
+
+/// Everything that can go wrong while getting data, nothing here exits the process
+#[derive(Debug)]
+pub enum ResponseErr {
+  /// Search engine asked to solve a captcha
+  Captcha,
+  /// Search engine refused to answer without JavaScript
+  JsEnforcement,
+  /// Google asked for cookie consent, and it could not be given
+  Consent,
+  /// Google showed a page (like "Before you continue") instead of results
+  Interstitial,
+  /// Google took the requests for unusual traffic (its `sorry` page)
+  Blocked,
+  /// The page was fetched, but there is nothing usable in it
+  NoEnoughData,
+  /// Songs were found, but none has the asked metadata
+  NoMatch,
+  /// The given URL is not handled by the provider
+  InvalidUrl(String),
+  /// The request could not be built or sent
+  Network(reqwest::Error),
+  /// The request took longer than the configured timeout
+  Timeout(reqwest::Error),
+  /// The server answered with a non-success status code
+  HttpStatus(reqwest::StatusCode),
+  /// The server asked to slow down (status 429), with the wait it asked for
+  RateLimited(Option<std::time::Duration>),
+  /// The call (every try included) took longer than the configured deadline
+  DeadlineExceeded,
+  /// The request failed through `proxy`, the next proxy is used from now on
+  ProxyFailed {
+    proxy: String,
+    source: Box<ResponseErr>,
+  },
+  /// The response does not have the expected structure
+  Parse {
+    context: &'static str,
+    source: Option<Box<dyn Error + Send + Sync>>,
+  },
+  /// Offline mode, and the data is not in the cache
+  NotCached,
+}
+
+impl ResponseErr {
+  pub(crate) fn parse(context: &'static str) -> Self {
+    Self::Parse {
+      context,
+      source: None,
+    }
+  }
+
+  pub(crate) fn parse_with<E: Error + Send + Sync + 'static>(context: &'static str, source: E) -> Self {
+    Self::Parse {
+      context,
+      source: Some(Box::new(source)),
+    }
+  }
+
+  /// Whether trying again the same request may give another result,
+  /// captchas, invalid URLs and the like are fatal
+  pub(crate) fn is_retryable(&self) -> bool {
+    match self {
+      Self::Network(_) | Self::Timeout(_) | Self::RateLimited(_) | Self::ProxyFailed { .. } => true,
+      Self::HttpStatus(code) => code.is_server_error() || *code == reqwest::StatusCode::REQUEST_TIMEOUT,
+      // Search results vary between requests
+      Self::NoEnoughData => true,
+      _ => false,
+    }
+  }
+}
+
+impl Display for ResponseErr {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Captcha => write!(f, "Captcha triggered, page has no data"),
+      Self::JsEnforcement => write!(f, "JavaScript enforcement, page has no data"),
+      Self::Consent => write!(f, "Cookie consent asked, and it could not be given"),
+      Self::Interstitial => write!(f, "Interstitial page instead of results"),
+      Self::Blocked => write!(f, "Blocked for unusual traffic"),
+      Self::NoEnoughData => write!(f, "There are no results for this query"),
+      Self::NoMatch => write!(f, "No song found matches the given fields"),
+      Self::InvalidUrl(url) => write!(f, "Not a supported lyrics URL: {url}"),
+      Self::Network(_) => write!(f, "Could not send request"),
+      Self::Timeout(_) => write!(f, "Request timed out"),
+      Self::HttpStatus(code) => write!(f, "Server answered with status {code}"),
+      Self::RateLimited(Some(after)) => write!(f, "Too many requests, asked to wait {}s", after.as_secs()),
+      Self::RateLimited(None) => write!(f, "Too many requests"),
+      Self::DeadlineExceeded => write!(f, "Deadline exceeded"),
+      Self::ProxyFailed { proxy, .. } => write!(f, "Request through proxy {proxy} failed"),
+      Self::Parse {
+        context,
+        source: None,
+      } => write!(f, "Invalid response structure: {context}"),
+      Self::Parse {
+        context,
+        source: Some(e),
+      } => write!(f, "Invalid response structure: {context}: {e}"),
+      Self::NotCached => write!(f, "Not in the cache, and requests are disabled (offline mode)"),
+    }
+  }
+}
+
+impl Error for ResponseErr {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match self {
+      Self::Network(e) | Self::Timeout(e) => Some(e),
+      Self::ProxyFailed { source, .. } => Some(source.as_ref()),
+      Self::Parse {
+        source: Some(e), ..
+      } => Some(e.as_ref()),
+      _ => None,
+    }
+  }
+}
+
+impl From<reqwest::Error> for ResponseErr {
+  fn from(e: reqwest::Error) -> Self {
+    if e.is_timeout() {
+      return Self::Timeout(e);
+    }
+    match e.status() {
+      Some(code) => Self::HttpStatus(code),
+      None => Self::Network(e),
+    }
+  }
+}
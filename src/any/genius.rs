@@ -0,0 +1,259 @@
+use reqwest::Url;
+use scraper::{ElementRef, Html, Node, Selector};
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::any::{
+  error::ResponseErr,
+  link,
+  provider::LyricsProvider,
+  proxy::{BlockingClients, ProxyPool},
+  track::{LyricParagraph, LyricsStatus, TrackInfo, TrackItem},
+  uagent,
+};
+
+/// Typed view of the `/api/search/song` response, only the parts in use
+#[derive(Deserialize)]
+struct SearchResponse {
+  response: SearchSections,
+}
+
+#[derive(Deserialize)]
+struct SearchSections {
+  sections: Vec<SearchSection>,
+}
+
+#[derive(Deserialize)]
+struct SearchSection {
+  #[serde(default)]
+  hits: Vec<SearchHit>,
+}
+
+#[derive(Deserialize)]
+struct SearchHit {
+  result: SongResult,
+}
+
+#[derive(Deserialize)]
+struct SongResult {
+  url: String,
+  full_title: String,
+}
+
+/// Client for Genius, lyrics are scraped from the song page
+pub(crate) struct GeniusAPI {
+  base_url: String,
+  timeout: u32,
+  clients: BlockingClients,
+}
+
+impl GeniusAPI {
+  pub(crate) const NAME: &'static str = "genius";
+
+  pub(crate) fn new(base_url: &str, timeout: u32) -> Self {
+    Self {
+      base_url: base_url.trim_end_matches('/').to_string(),
+      timeout,
+      clients: BlockingClients::default(),
+    }
+  }
+
+  /// Send every request through the proxies of `pool`
+  pub(crate) fn proxies(mut self, pool: Arc<ProxyPool>) -> Self {
+    self.clients = BlockingClients::new(Some(pool));
+    self
+  }
+
+  fn get_text(&self, url: Url) -> Result<String, ResponseErr> {
+    self.clients.send(|client| {
+      Ok(
+        client
+          .get(url.clone())
+          .header(reqwest::header::USER_AGENT, uagent::random())
+          .timeout(Duration::from_millis(self.timeout as u64))
+          .send()?
+          .error_for_status()?
+          .text()?,
+      )
+    })
+  }
+
+  /// Genius always answers with genius.com URLs, keep them on the configured base
+  fn rebase(&self, url: &str) -> String {
+    match Url::parse(url) {
+      Ok(u) => format!("{}{}", self.base_url, u.path()),
+      Err(_) => url.to_string(),
+    }
+  }
+}
+
+impl LyricsProvider for GeniusAPI {
+  fn name(&self) -> &'static str {
+    Self::NAME
+  }
+
+  fn handles_url(&self, url: &str) -> bool {
+    link::genius(url, &self.base_url).is_some()
+  }
+
+  fn search(&self, query: &str) -> Result<Vec<TrackItem>, ResponseErr> {
+    let endpoint = format!("{}/api/search/song", self.base_url);
+    let url =
+      Url::parse_with_params(&endpoint, &[("q", query), ("per_page", "10")]).map_err(|_| ResponseErr::InvalidUrl(endpoint))?;
+
+    let body = self.get_text(url)?;
+    let mut de = serde_json::Deserializer::from_str(&body);
+    let res: SearchResponse =
+      serde_path_to_error::deserialize(&mut de).map_err(|e| ResponseErr::parse_with("Genius search response", e))?;
+
+    Ok(
+      res
+        .response
+        .sections
+        .into_iter()
+        .flat_map(|s| s.hits)
+        .map(|h| TrackItem::new(self.rebase(&h.result.url), h.result.full_title))
+        .collect(),
+    )
+  }
+
+  fn fetch(&self, url: &str) -> Result<TrackInfo, ResponseErr> {
+    let link = link::genius(url, &self.base_url).ok_or(ResponseErr::InvalidUrl(url.into()))?;
+    let parsed = Url::parse(&link.url).map_err(|_| ResponseErr::InvalidUrl(url.into()))?;
+    let html = self.get_text(parsed)?;
+    Ok(parse_song_page(&html, &link.url))
+  }
+}
+
+/// Read the song page: metadata from the head, lyrics from the lyrics containers
+fn parse_song_page(html: &str, url: &str) -> TrackInfo {
+  let doc = Html::parse_document(html);
+  let container = Selector::parse(r#"div[data-lyrics-container="true"]"#).unwrap();
+  let title = Selector::parse("title").unwrap();
+  let og_image = Selector::parse(r#"meta[property="og:image"]"#).unwrap();
+
+  // Like "Linkin Park – Numb Lyrics | Genius Lyrics"
+  let page_title = doc
+    .select(&title)
+    .next()
+    .map(|t| t.text().collect::<String>())
+    .unwrap_or_default();
+  let page_title = page_title.split(" | ").next().unwrap_or_default();
+  let page_title = page_title.strip_suffix(" Lyrics").unwrap_or(page_title);
+  let (artist, name) = page_title
+    .split_once(" \u{2013} ")
+    .map(|(a, n)| (a.trim().to_string(), n.trim().to_string()))
+    .unwrap_or_else(|| (String::new(), page_title.trim().to_string()));
+
+  let cover = doc
+    .select(&og_image)
+    .next()
+    .and_then(|m| m.value().attr("content"))
+    .map(String::from);
+
+  let mut text = String::new();
+  for div in doc.select(&container) {
+    collect_text(div, &mut text);
+    text.push('\n');
+  }
+  let paragraphs = split_sections(&text);
+  let lyrics = paragraphs
+    .iter()
+    .map(|p| p.lines.join("\n"))
+    .collect::<Vec<String>>()
+    .join("\n\n");
+
+  let lyrics_status = if !lyrics.trim().is_empty() {
+    LyricsStatus::Available
+  } else if html.contains("This song is an instrumental") {
+    LyricsStatus::Instrumental
+  } else {
+    LyricsStatus::Missing
+  };
+
+  TrackInfo {
+    name,
+    artist,
+    album: None,
+    album_url: None,
+    lyrics_status,
+    has_lyrics_struct: paragraphs.iter().any(|p| !p.title.is_empty()),
+    lyrics_lang: None,
+    lyrics_lang_code: None,
+    lyrics,
+    lyrics_struct: paragraphs,
+    synced: None,
+    rich_synced: None,
+    lyrics_copyright: None,
+    genre: None,
+    cover,
+    released: None,
+    duration: None,
+    isrc: None,
+    explicit: false,
+    instrumental: lyrics_status == LyricsStatus::Instrumental,
+    writers: Vec::new(),
+    publishers: Vec::new(),
+    track_id: None,
+    spotify: None,
+    url: url.to_string(),
+    source: GeniusAPI::NAME,
+  }
+}
+
+/// Plain text of a lyrics container: `<br>` become line breaks, annotation
+/// links keep only their text, and page widgets inside it are skipped
+fn collect_text(element: ElementRef, out: &mut String) {
+  for child in element.children() {
+    match child.value() {
+      Node::Text(t) => out.push_str(t),
+      Node::Element(e) if e.name() == "br" => out.push('\n'),
+      Node::Element(e) if e.attr("data-exclude-from-selection").is_some() => (),
+      Node::Element(_) => {
+        if let Some(el) = ElementRef::wrap(child) {
+          collect_text(el, out);
+        }
+      }
+      _ => (),
+    }
+  }
+}
+
+/// Split lyrics text into paragraphs, using `[Chorus]` like headers as
+/// titles and empty lines as paragraph ends
+fn split_sections(text: &str) -> Vec<LyricParagraph> {
+  let mut paragraphs: Vec<LyricParagraph> = Vec::new();
+  let mut current = LyricParagraph {
+    lines: Vec::new(),
+    title: String::new(),
+  };
+
+  for line in text.lines().map(str::trim) {
+    let header = line.strip_prefix('[').and_then(|l| l.strip_suffix(']'));
+    if header.is_none() && !line.is_empty() {
+      current.lines.push(line.to_string());
+      continue;
+    }
+
+    let title = header.map(String::from).unwrap_or_default();
+    let done = std::mem::replace(
+      &mut current,
+      LyricParagraph {
+        lines: Vec::new(),
+        title,
+      },
+    );
+    if !done.lines.is_empty() {
+      paragraphs.push(done);
+    } else if header.is_none() {
+      // Empty line right after a header, keep the header
+      current.title = done.title;
+    }
+  }
+
+  if !current.lines.is_empty() {
+    paragraphs.push(current);
+  }
+  paragraphs
+}
@@ -0,0 +1,77 @@
+use reqwest::Url;
+use reqwest_cookie_store::{CookieStore, CookieStoreMutex};
+use std::fs::{File, OpenOptions};
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Cookies shared by every request of a client, kept in a json file
+/// between runs (Google consent and session cookies included)
+#[derive(Default)]
+pub(crate) struct CookieJar {
+  store: Arc<CookieStoreMutex>,
+  /// In memory only when `None`
+  path: Option<PathBuf>,
+}
+
+impl CookieJar {
+  /// Load the jar at `path`, a missing or unreadable file gives an empty
+  /// jar (overwritten on the next save)
+  pub(crate) fn load(path: PathBuf) -> Self {
+    let store = File::open(&path)
+      .ok()
+      .and_then(|f| cookie_store::serde::json::load(BufReader::new(f)).ok())
+      .unwrap_or_default();
+
+    Self {
+      store: Arc::new(CookieStoreMutex::new(store)),
+      path: Some(path),
+    }
+  }
+
+  pub(crate) fn store(&self) -> Arc<CookieStoreMutex> {
+    self.store.clone()
+  }
+
+  /// Add the `name=value; ...` pairs of a Cookie header as if `url` had
+  /// set them, so they are only sent back to its host
+  pub(crate) fn add_header(&self, header: &str, url: &str) -> Result<(), String> {
+    let url = Url::parse(url).map_err(|e| format!("Invalid cookie site '{url}': {e}"))?;
+    let mut store = self.store.lock().unwrap();
+    for pair in header.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+      store
+        .parse(pair, &url)
+        .map_err(|e| format!("Invalid cookie '{pair}': {e}"))?;
+    }
+    Ok(())
+  }
+
+  pub(crate) fn save(&self) -> Result<(), String> {
+    let Some(path) = &self.path else {
+      return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+      std::fs::create_dir_all(dir).map_err(|e| format!("Could not create {}: {e}", dir.display()))?;
+    }
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    // Session cookies are as good as a login, only the owner may read them
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+      .open(path)
+      .map_err(|e| format!("Could not write {}: {e}", path.display()))?;
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::PermissionsExt;
+      // Files saved before are tightened too
+      file
+        .set_permissions(std::fs::Permissions::from_mode(0o600))
+        .map_err(|e| format!("Could not restrict {}: {e}", path.display()))?;
+    }
+    let store: &CookieStore = &self.store.lock().unwrap();
+    // Session cookies too, expired ones are dropped when loading
+    cookie_store::serde::json::save_incl_expired_and_nonpersistent(store, &mut file)
+      .map_err(|e| format!("Could not save cookies to {}: {e}", path.display()))
+  }
+}
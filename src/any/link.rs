@@ -0,0 +1,195 @@
+use reqwest::Url;
+
+use crate::any::endpoints::Endpoints;
+
+/// Public Musixmatch site, search engines give URLs on it
+pub(crate) const MUSIXMATCH_URL: &str = "https://www.musixmatch.com";
+const SPOTIFY_URL: &str = "https://open.spotify.com";
+
+/// What a pasted URL points to
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum LinkKind {
+  /// Musixmatch song page, with the language if it was a translation page
+  MxmTrack {
+    translation: Option<String>,
+  },
+  MxmAlbum,
+  MxmArtist,
+  SpotifyTrack,
+  GeniusSong,
+  LrclibTrack,
+}
+
+impl LinkKind {
+  pub(crate) fn name(&self) -> &'static str {
+    match self {
+      Self::MxmTrack { .. } => "Musixmatch song",
+      Self::MxmAlbum => "Musixmatch album",
+      Self::MxmArtist => "Musixmatch artist",
+      Self::SpotifyTrack => "Spotify track",
+      Self::GeniusSong => "Genius song",
+      Self::LrclibTrack => "LRCLIB track",
+    }
+  }
+
+  /// Whether a lyrics provider can fetch it as is
+  pub(crate) fn is_song(&self) -> bool {
+    matches!(
+      self,
+      Self::MxmTrack { .. } | Self::GeniusSong | Self::LrclibTrack
+    )
+  }
+}
+
+/// A recognised URL, in its canonical form: on the configured base, no
+/// query, fragment, trailing slash nor page variant (like `/embed`)
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Link {
+  pub(crate) kind: LinkKind,
+  pub(crate) url: String,
+}
+
+impl Link {
+  /// Artist page of a Musixmatch song, album or artist link
+  pub(crate) fn mxm_artist(&self) -> Option<String> {
+    let prefix = match self.kind {
+      LinkKind::MxmTrack { .. } => "/lyrics/",
+      LinkKind::MxmAlbum => "/album/",
+      LinkKind::MxmArtist => return Some(self.url.clone()),
+      _ => return None,
+    };
+    // Like <base>/lyrics/<artist>/<title>
+    let (base, path) = self.url.rsplit_once(prefix)?;
+    let artist = path.split('/').next()?;
+    Some(format!("{base}/artist/{artist}"))
+  }
+}
+
+/// Recognise a URL of any supported site, public or at its configured base
+pub(crate) fn classify(url: &str, endpoints: &Endpoints) -> Option<Link> {
+  musixmatch(url, &endpoints.musixmatch)
+    .or_else(|| genius(url, &endpoints.genius))
+    .or_else(|| lrclib(url, &endpoints.lrclib))
+    .or_else(|| spotify(url))
+}
+
+/// Musixmatch track, album or artist pages, country subdomains and locale
+/// prefixes (like `/es/lyrics/...`) included
+pub(crate) fn musixmatch(url: &str, base: &str) -> Option<Link> {
+  let segments = site_path(url, "musixmatch.com", base)?;
+  let mut segments = segments.iter().map(String::as_str).peekable();
+  if segments.peek().is_some_and(|s| is_locale(s)) {
+    segments.next();
+  }
+
+  let base = base.trim_end_matches('/');
+  let (kind, url) = match (segments.next()?, segments.next()?, segments.next()) {
+    ("lyrics", artist, Some(title)) => {
+      let translation = match (segments.next(), segments.next()) {
+        (Some("translation"), Some(lang)) => Some(lang.to_string()),
+        _ => None,
+      };
+      (
+        LinkKind::MxmTrack { translation },
+        format!("{base}/lyrics/{artist}/{title}"),
+      )
+    }
+    ("album", artist, Some(album)) => (LinkKind::MxmAlbum, format!("{base}/album/{artist}/{album}")),
+    ("artist", artist, _) => (LinkKind::MxmArtist, format!("{base}/artist/{artist}")),
+    _ => return None,
+  };
+  Some(Link { kind, url })
+}
+
+/// Genius song pages, like `/Linkin-park-numb-lyrics`
+pub(crate) fn genius(url: &str, base: &str) -> Option<Link> {
+  match site_path(url, "genius.com", base)?.as_slice() {
+    [slug] if slug.len() > 7 && slug.ends_with("-lyrics") => Some(Link {
+      kind: LinkKind::GeniusSong,
+      url: format!("{}/{slug}", base.trim_end_matches('/')),
+    }),
+    _ => None,
+  }
+}
+
+/// LRCLIB records, like `/api/get/123`
+pub(crate) fn lrclib(url: &str, base: &str) -> Option<Link> {
+  match site_path(url, "lrclib.net", base)?.as_slice() {
+    [api, get, id] if api == "api" && get == "get" && !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()) => Some(Link {
+      kind: LinkKind::LrclibTrack,
+      url: format!("{}/api/get/{id}", base.trim_end_matches('/')),
+    }),
+    _ => None,
+  }
+}
+
+/// Spotify track pages, localized (`/intl-es/track/...`) and embed ones
+/// too, or `spotify:track:<id>` URIs
+pub(crate) fn spotify(url: &str) -> Option<Link> {
+  let track = |id: &str| {
+    (id.len() == 22 && id.bytes().all(|b| b.is_ascii_alphanumeric())).then(|| Link {
+      kind: LinkKind::SpotifyTrack,
+      url: format!("{SPOTIFY_URL}/track/{id}"),
+    })
+  };
+  if let Some(id) = url.trim().strip_prefix("spotify:track:") {
+    return track(id);
+  }
+
+  let segments = site_path(url, "open.spotify.com", SPOTIFY_URL)?;
+  let mut segments = segments
+    .iter()
+    .map(String::as_str)
+    .skip_while(|s| s.starts_with("intl-") || *s == "embed");
+  match (segments.next()?, segments.next()?) {
+    ("track", id) => track(id),
+    _ => None,
+  }
+}
+
+/// Non empty path segments of `url` when it is on `host` (or a subdomain),
+/// or under `base`. URLs without a scheme are taken as `https`
+fn site_path(url: &str, host: &str, base: &str) -> Option<Vec<String>> {
+  let url = url.trim();
+  if url.contains(char::is_whitespace) {
+    return None;
+  }
+  let url = match url.contains("://") {
+    true => Url::parse(url),
+    false => Url::parse(&format!("https://{url}")),
+  }
+  .ok()?;
+  if !matches!(url.scheme(), "http" | "https") {
+    return None;
+  }
+
+  let url_host = url.host_str()?.to_lowercase();
+  let path = if url_host == host || url_host.ends_with(&format!(".{host}")) {
+    url.path()
+  } else {
+    let base = Url::parse(base).ok()?;
+    if base.host_str() != Some(url_host.as_str()) || base.port_or_known_default() != url.port_or_known_default() {
+      return None;
+    }
+    url.path().strip_prefix(base.path().trim_end_matches('/'))?
+  };
+
+  Some(
+    path
+      .split('/')
+      .filter(|s| !s.is_empty())
+      .map(String::from)
+      .collect(),
+  )
+}
+
+/// Like `es` or `pt-br`
+fn is_locale(segment: &str) -> bool {
+  let mut parts = segment.split('-');
+  let lang = parts.next().unwrap_or_default();
+  let region = parts.next();
+  lang.len() == 2
+    && lang.bytes().all(|b| b.is_ascii_lowercase())
+    && region.is_none_or(|r| r.len() == 2 && r.bytes().all(|b| b.is_ascii_alphabetic()))
+    && parts.next().is_none()
+}
@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use crate::any::track::{RichSyncLine, SyncedLine, TrackInfo};
+
+/// Format a time as a LRC timestamp body: `mm:ss.xx`
+pub(crate) fn timestamp(time: Duration) -> String {
+  let hundredths = time.as_millis() / 10;
+  format!(
+    "{:02}:{:02}.{:02}",
+    hundredths / 6000,
+    (hundredths / 100) % 60,
+    hundredths % 100
+  )
+}
+
+/// Parse a LRC timestamp body (`mm:ss.xx`, `mm:ss.xxx` or `mm:ss`)
+fn parse_timestamp(stamp: &str) -> Option<Duration> {
+  let (min, sec) = stamp.split_once(':')?;
+  let min: u64 = min.trim().parse().ok()?;
+  let sec: f64 = sec.trim().parse().ok()?;
+  if !sec.is_finite() || sec < 0.0 {
+    return None;
+  }
+  Some(Duration::from_secs(min * 60) + Duration::from_secs_f64(sec))
+}
+
+/// Parse standard LRC into synced lines, sorted by time. Metadata tags
+/// (like `[ar:...]`) are skipped, lines with many timestamps are repeated
+pub(crate) fn parse(lrc: &str) -> Vec<SyncedLine> {
+  let mut lines = Vec::new();
+  for raw in lrc.lines() {
+    let mut rest = raw.trim();
+    let mut times = Vec::new();
+    while let Some(tag) = rest.strip_prefix('[') {
+      let Some(end) = tag.find(']') else { break };
+      let Some(time) = parse_timestamp(&tag[..end]) else { break };
+      times.push(time);
+      rest = &tag[end + 1..];
+    }
+    for time in times {
+      lines.push(SyncedLine {
+        time,
+        text: rest.trim().to_string(),
+        section: None,
+      });
+    }
+  }
+  lines.sort_by_key(|l| l.time);
+  lines
+}
+
+/// Serialize the synced lyrics of a track as standard LRC, with an empty
+/// line between sections. `None` if the track has no synced lyrics
+pub(crate) fn to_lrc(track: &TrackInfo) -> Option<String> {
+  let lines = track.synced.as_ref()?;
+
+  let mut lrc = header(track);
+
+  let mut section = None;
+  for SyncedLine {
+    time,
+    text,
+    section: line_section,
+  } in lines
+  {
+    if line_section.is_some() && section.is_some() && *line_section != section {
+      lrc.push('\n');
+    }
+    if line_section.is_some() {
+      section = *line_section;
+    }
+    lrc.push_str(&format!("[{}]{}\n", timestamp(*time), text));
+  }
+
+  Some(lrc)
+}
+
+/// Serialize the word-synced lyrics of a track as Enhanced LRC, every word
+/// gets a `<mm:ss.xx>` tag. `None` if the track has no word-synced lyrics
+pub(crate) fn to_enhanced_lrc(track: &TrackInfo) -> Option<String> {
+  let lines = track.rich_synced.as_ref()?;
+
+  let mut lrc = header(track);
+  for RichSyncLine { start, end, words } in lines {
+    lrc.push_str(&format!("[{}]", timestamp(*start)));
+    for (offset, word) in words {
+      if word.trim().is_empty() {
+        lrc.push_str(word);
+      } else {
+        lrc.push_str(&format!("<{}>{}", timestamp(*start + *offset), word));
+      }
+    }
+    lrc.push_str(&format!("<{}>\n", timestamp(*end)));
+  }
+
+  Some(lrc)
+}
+
+fn header(track: &TrackInfo) -> String {
+  let mut header = format!(
+    "[ti:{}]\n[ar:{}]\n[al:{}]\n",
+    track.name,
+    track.artist,
+    track.album.as_deref().unwrap_or_default()
+  );
+  if let Some(secs) = track.duration {
+    header.push_str(&format!("[length:{:02}:{:02}]\n", secs / 60, secs % 60));
+  }
+  header.push('\n');
+  header
+}
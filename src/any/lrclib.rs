@@ -0,0 +1,205 @@
+use reqwest::Url;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::any::{
+  error::ResponseErr,
+  link, lrc,
+  provider::LyricsProvider,
+  proxy::{BlockingClients, ProxyPool},
+  track::{LyricsStatus, TrackInfo, TrackItem, TrackQuery},
+};
+
+/// LRCLIB asks clients to identify themselves
+const USER_AGENT: &str = concat!(
+  env!("CARGO_PKG_NAME"),
+  " v",
+  env!("CARGO_PKG_VERSION"),
+  " (https://github.com/klapptnot/lyrics)"
+);
+
+/// A song record, as returned by the LRCLIB JSON API
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LrclibRecord {
+  id: u64,
+  track_name: String,
+  artist_name: String,
+  album_name: Option<String>,
+  /// Song length in seconds
+  duration: Option<f64>,
+  #[serde(default)]
+  instrumental: bool,
+  plain_lyrics: Option<String>,
+  synced_lyrics: Option<String>,
+}
+
+/// Client for LRCLIB (or any server with the same API), lyrics come
+/// straight from a JSON API, no search engine involved
+pub(crate) struct LrclibAPI {
+  base_url: String,
+  timeout: u32,
+  clients: BlockingClients,
+}
+
+impl LrclibAPI {
+  pub(crate) const NAME: &'static str = "lrclib";
+
+  pub(crate) fn new(base_url: &str, timeout: u32) -> Self {
+    Self {
+      base_url: base_url.trim_end_matches('/').to_string(),
+      timeout,
+      clients: BlockingClients::default(),
+    }
+  }
+
+  /// Send every request through the proxies of `pool`
+  pub(crate) fn proxies(mut self, pool: Arc<ProxyPool>) -> Self {
+    self.clients = BlockingClients::new(Some(pool));
+    self
+  }
+
+  fn endpoint(&self, path: &str, params: &[(&str, String)]) -> Result<Url, ResponseErr> {
+    let url = format!("{}{}", self.base_url, path);
+    Url::parse_with_params(&url, params).map_err(|_| ResponseErr::InvalidUrl(url))
+  }
+
+  fn get_json<T: serde::de::DeserializeOwned>(&self, url: &Url) -> Result<T, ResponseErr> {
+    let body = self.clients.send(|client| {
+      Ok(
+        client
+          .get(url.clone())
+          .header(reqwest::header::USER_AGENT, USER_AGENT)
+          .timeout(Duration::from_millis(self.timeout as u64))
+          .send()?
+          .error_for_status()?
+          .text()?,
+      )
+    })?;
+
+    let mut de = serde_json::Deserializer::from_str(&body);
+    serde_path_to_error::deserialize(&mut de).map_err(|e| ResponseErr::parse_with("LRCLIB response", e))
+  }
+
+  fn track_item(&self, record: &LrclibRecord) -> TrackItem {
+    let desc = match &record.album_name {
+      Some(album) if !album.is_empty() => format!("{} - {} ({})", record.artist_name, record.track_name, album),
+      _ => format!("{} - {}", record.artist_name, record.track_name),
+    };
+    TrackItem::new(format!("{}/api/get/{}", self.base_url, record.id), desc)
+  }
+
+  fn track_info(&self, record: LrclibRecord) -> TrackInfo {
+    let synced = record
+      .synced_lyrics
+      .as_deref()
+      .map(lrc::parse)
+      .filter(|l| !l.is_empty());
+    let lyrics = match record.plain_lyrics {
+      Some(plain) if !plain.trim().is_empty() => Some(plain),
+      _ => synced
+        .as_ref()
+        .map(|l| l.iter().map(|l| l.text.as_str()).collect::<Vec<_>>().join("\n")),
+    };
+
+    TrackInfo {
+      name: record.track_name,
+      artist: record.artist_name,
+      album: record.album_name,
+      album_url: None,
+      lyrics_status: match (record.instrumental, &lyrics) {
+        (true, _) => LyricsStatus::Instrumental,
+        (false, Some(_)) => LyricsStatus::Available,
+        (false, None) => LyricsStatus::Missing,
+      },
+      has_lyrics_struct: false,
+      lyrics_lang: None,
+      lyrics_lang_code: None,
+      lyrics: lyrics.unwrap_or_default(),
+      lyrics_struct: Vec::new(),
+      synced,
+      rich_synced: None,
+      lyrics_copyright: None,
+      genre: None,
+      cover: None,
+      released: None,
+      duration: record.duration.map(|d| d.round() as u32),
+      isrc: None,
+      explicit: false,
+      instrumental: record.instrumental,
+      writers: Vec::new(),
+      publishers: Vec::new(),
+      track_id: Some(record.id),
+      spotify: None,
+      url: format!("{}/api/get/{}", self.base_url, record.id),
+      source: Self::NAME,
+    }
+  }
+}
+
+impl LyricsProvider for LrclibAPI {
+  fn name(&self) -> &'static str {
+    Self::NAME
+  }
+
+  fn handles_url(&self, url: &str) -> bool {
+    link::lrclib(url, &self.base_url).is_some()
+  }
+
+  /// Search by song metadata. With title, artist and duration the exact
+  /// signature endpoint is used, otherwise the search one, which needs a
+  /// title or else free text
+  fn search_fields(&self, query: &TrackQuery) -> Result<Vec<TrackItem>, ResponseErr> {
+    let mut params = Vec::new();
+    match &query.title {
+      Some(title) => params.push(("track_name", title.clone())),
+      None => params.push(("q", query.text())),
+    }
+    if let Some(artist) = &query.artist {
+      params.push(("artist_name", artist.clone()));
+    }
+    if let Some(album) = &query.album {
+      params.push(("album_name", album.clone()));
+    }
+
+    let mut records: Vec<LrclibRecord> = match query.duration {
+      Some(duration) if query.title.is_some() && query.artist.is_some() => {
+        params.push(("duration", duration.to_string()));
+        match self.get_json(&self.endpoint("/api/get", &params)?) {
+          Ok(record) => vec![record],
+          Err(ResponseErr::HttpStatus(code)) if code == reqwest::StatusCode::NOT_FOUND => Vec::new(),
+          Err(e) => return Err(e),
+        }
+      }
+      _ => self.get_json(&self.endpoint("/api/search", &params)?)?,
+    };
+
+    // LRCLIB matches durations with a 2 seconds tolerance
+    if let Some(duration) = query.duration {
+      records.retain(|r| r.duration.is_none_or(|d| (d - duration as f64).abs() <= 2.0));
+    }
+
+    Ok(records.iter().map(|r| self.track_item(r)).collect())
+  }
+
+  /// `Artist - Title` queries are searched by field, anything else as free text
+  fn search(&self, query: &str) -> Result<Vec<TrackItem>, ResponseErr> {
+    if let Some((artist, title)) = query.split_once(" - ") {
+      return self.search_fields(&TrackQuery {
+        title: Some(title.trim().into()),
+        artist: Some(artist.trim().into()),
+        ..Default::default()
+      });
+    }
+
+    let records: Vec<LrclibRecord> = self.get_json(&self.endpoint("/api/search", &[("q", query.into())])?)?;
+    Ok(records.iter().map(|r| self.track_item(r)).collect())
+  }
+
+  fn fetch(&self, url: &str) -> Result<TrackInfo, ResponseErr> {
+    let link = link::lrclib(url, &self.base_url).ok_or(ResponseErr::InvalidUrl(url.into()))?;
+    let url = Url::parse(&link.url).map_err(|_| ResponseErr::InvalidUrl(url.into()))?;
+    Ok(self.track_info(self.get_json(&url)?))
+  }
+}
@@ -1,4 +1,19 @@
+pub(crate) mod cache;
+pub(crate) mod endpoints;
+pub(crate) mod error;
+pub(crate) mod genius;
+pub(crate) mod jar;
+pub(crate) mod link;
+pub(crate) mod lrc;
+pub(crate) mod lrclib;
 pub(crate) mod macros;
 pub(crate) mod mxm;
+pub(crate) mod provider;
+pub(crate) mod proxy;
+pub(crate) mod rank;
+pub(crate) mod retry;
+pub(crate) mod search;
 pub(crate) mod spinner;
+pub(crate) mod spotify;
+pub(crate) mod track;
 pub(crate) mod uagent;
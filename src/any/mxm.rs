@@ -1,384 +1,1209 @@
-use reqwest::header::HeaderMap;
-use serde_json::Value;
+use reqwest::{header::HeaderMap, Url};
+use serde::{de, de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tokio::time::Instant;
 
-use crate::any::{macros, spinner::Spinner};
+use crate::any::{
+  cache::{self, Cache, CacheMode, EntryKind},
+  endpoints::Endpoints,
+  error::ResponseErr,
+  jar::CookieJar,
+  link::{self, Link, LinkKind},
+  macros,
+  provider::LyricsProvider,
+  proxy::{self, ProxyPool},
+  retry::{check_status, RetryPolicy},
+  search::{self, ConsentForm, SearchBackend},
+  spinner::Spinner,
+  track::{AlbumInfo, AlbumItem, ArtistInfo, LyricParagraph, LyricsStatus, RichSyncLine, SyncedLine, TrackInfo, TrackItem},
+};
 
-pub(crate) struct TrackItem {
-  pub(crate) url: String,
-  pub(crate) desc: String,
+/// Translated lyrics, aligned to the original lines
+pub(crate) struct Translation {
+  /// Language code of the translation
+  pub(crate) lang: String,
+  /// Translated line for each original line
+  lines: HashMap<String, String>,
 }
 
-impl TrackItem {
-  pub(crate) fn new(url: String, desc: String) -> Self {
-    Self { url, desc }
+impl Translation {
+  /// Translation of an original lyrics line
+  pub(crate) fn line(&self, original: &str) -> Option<&str> {
+    self.lines.get(original.trim()).map(String::as_str)
+  }
+
+  pub(crate) fn is_empty(&self) -> bool {
+    self.lines.is_empty()
+  }
+
+  fn from_json(json: &str, lang: &str) -> Result<Self, serde_path_to_error::Error<serde_json::Error>> {
+    let mut de = serde_json::Deserializer::from_str(json);
+    let data: NextData<TranslationPageData> = serde_path_to_error::deserialize(&mut de)?;
+
+    let lines = data
+      .props
+      .page_props
+      .data
+      .track_info
+      .data
+      .translations_list
+      .into_iter()
+      .filter(|t| t.translation.language.eq_ignore_ascii_case(lang))
+      .map(|t| {
+        (
+          t.translation.matched_line.trim().into(),
+          t.translation.description,
+        )
+      })
+      .collect();
+
+    Ok(Self {
+      lang: lang.into(),
+      lines,
+    })
   }
 }
+/// Typed view of the `__NEXT_DATA__` payload of a Musixmatch page, `T` is
+/// the page specific data, only the parts in use are declared
+#[derive(Deserialize)]
+struct NextData<T> {
+  props: NextProps<T>,
+}
 
-pub(crate) struct LyricParagraph {
-  pub(crate) lines: Vec<String>,
-  pub(crate) title: String, // Discard type, its always lyrics
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NextProps<T> {
+  page_props: PageProps<T>,
 }
 
-impl LyricParagraph {
-  fn vec_of(data: &Value) -> Vec<LyricParagraph> {
-    let mut me: Vec<LyricParagraph> = Vec::new();
-    if let Value::Array(data) = data {
-      for lp in data {
-        me.push(LyricParagraph {
-          lines: lp["lines"]
-            .as_array()
-            .unwrap()
-            .iter()
-            .map(|l| Value::as_str(&l["text"]).unwrap().into())
-            .collect::<Vec<String>>(),
-          title: Value::as_str(&lp["title"]).unwrap().into(),
-        });
-      }
-    }
-    me
-  }
-}
-
-/// Trying to not have this in main.rs, here is it
-pub(crate) struct TrackInfo {
-  /// Song title
-  pub(crate) name: String,
-  /// Song artist/group
-  pub(crate) artist: String,
-  /// Album containing the song
-  pub(crate) album: String,
-  /// Whether song has lyrics or lyrics is disponible
-  pub(crate) has_lyrics: bool,
-  /// Whether song has lyrics structure or not
-  pub(crate) has_lyrics_struct: bool,
-  /// Lyrics (full) language name
-  #[allow(dead_code)]
-  pub(crate) lyrics_lang: String,
-  /// The lyrics as string (escaped)
-  pub(crate) lyrics: String,
-  /// A structured lyrics representation
-  pub(crate) lyrics_struct: Vec<LyricParagraph>,
-  /// Lyric composer(s)
-  pub(crate) lyrics_copyright: String,
-  /// Song primary genre
-  pub(crate) genre: String,
-  /// Cover image URL (expect a 350x350 px jpg file url)
-  pub(crate) cover: String,
-  /// Song release date in format YYYY-MM-DD
-  pub(crate) released: String,
-  /// Spotify URL for this song
-  pub(crate) spotify: String,
-  /// Musixmatch URL for this song
-  pub(crate) musixmatch: String,
-}
-
-impl TrackInfo {
-  fn from(json: String) -> Option<Self> {
-    let data: Option<Value> = serde_json::from_str(json.as_str()).unwrap_or(None);
-    if let Some(data) = data {
-      return Some(Self {
-        name: Value::as_str(&data["props"]["pageProps"]["data"]["trackInfo"]["data"]["track"]["name"])
-          .unwrap_or("Unespecified")
-          .into(),
-        artist: Value::as_str(&data["props"]["pageProps"]["data"]["trackInfo"]["data"]["track"]["artistName"])
-          .unwrap_or("Unespecified")
-          .into(),
-        album: Value::as_str(&data["props"]["pageProps"]["data"]["trackInfo"]["data"]["track"]["albumName"])
-          .unwrap_or("Unespecified")
-          .into(),
-        has_lyrics: Value::as_bool(&data["props"]["pageProps"]["data"]["trackInfo"]["data"]["track"]["hasLyrics"])
-          .unwrap_or(false),
-        has_lyrics_struct: Value::as_bool(
-          &data["props"]["pageProps"]["data"]["trackInfo"]["data"]["track"]["hasTrackStructure"],
-        )
-        .unwrap_or(false),
-        lyrics_lang: Value::as_str(&data["props"]["pageProps"]["data"]["trackInfo"]["data"]["lyrics"]["languageDescription"])
-          .unwrap_or("Unespecified")
-          .into(),
-        lyrics: Value::as_str(&data["props"]["pageProps"]["data"]["trackInfo"]["data"]["lyrics"]["body"])
-          .unwrap_or("Unespecified")
-          .into(),
-        lyrics_struct: LyricParagraph::vec_of(&data["props"]["pageProps"]["data"]["trackInfo"]["data"]["trackStructureList"]),
-        lyrics_copyright: Value::as_str(&data["props"]["pageProps"]["data"]["trackInfo"]["data"]["lyrics"]["copyright"])
-          .unwrap_or("Unespecified")
-          .into(),
-        genre: Value::as_str(
-          &data["props"]["pageProps"]["data"]["trackInfo"]["data"]["track"]["artists"][0]["genres"][0]["name"],
-        )
-        .unwrap_or("Unespecified")
-        .into(),
-        cover: Value::as_str(&data["props"]["pageProps"]["data"]["trackInfo"]["data"]["track"]["coverImage"])
-          .unwrap_or("Unespecified")
-          .into(),
-        released: Value::as_str(&data["props"]["pageProps"]["data"]["trackInfo"]["data"]["track"]["releaseDate"])
-          .unwrap_or("0000-00-00")[0..10]
-          .into(),
-        spotify: if let Some(s) = Value::as_str(&data["props"]["pageProps"]["data"]["trackInfo"]["data"]["track"]["spotifyId"])
-        {
-          format!("https://open.spotify.com/track/{s}")
-        } else {
-          "Missing Spotify music ID".to_string()
-        },
-        musixmatch: if let Some(s) =
-          Value::as_str(&data["props"]["pageProps"]["data"]["trackInfo"]["data"]["track"]["vanityId"])
-        {
-          format!("https://musixmatch.com/lyrics/{s}")
-        } else {
-          "Missing Musixmatch ID".to_string()
-        },
-      });
-    }
-    return None;
+#[derive(Deserialize)]
+struct PageProps<T> {
+  data: T,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageData {
+  track_info: Payload<TrackInfoData>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AlbumPageData {
+  album_info: Payload<AlbumInfoData>,
+}
+
+#[derive(Deserialize)]
+struct AlbumInfoData {
+  album: AlbumData,
+  /// In tracklist order
+  #[serde(default)]
+  tracks: Vec<AlbumTrackData>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AlbumData {
+  name: String,
+  /// Missing in artist pages
+  #[serde(default)]
+  artist_name: String,
+  release_date: Option<String>,
+  vanity_id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AlbumTrackData {
+  name: String,
+  vanity_id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtistPageData {
+  artist_albums: Payload<ArtistAlbumsData>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtistAlbumsData {
+  artist: ArtistPageArtist,
+  #[serde(default)]
+  albums: Vec<AlbumData>,
+  /// Missing when everything fits in one page
+  total_pages: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct ArtistPageArtist {
+  name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TranslationPageData {
+  track_info: Payload<TranslationsData>,
+}
+
+#[derive(Deserialize)]
+struct Payload<T> {
+  data: T,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TranslationsData {
+  #[serde(default)]
+  translations_list: Vec<TranslationItem>,
+}
+
+#[derive(Deserialize)]
+struct TranslationItem {
+  translation: TranslationData,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TranslationData {
+  /// Language code, like `es`
+  language: String,
+  /// The original line
+  matched_line: String,
+  /// The translated line
+  description: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TrackInfoData {
+  track: TrackData,
+  /// Missing (or null) when the song has no lyrics
+  lyrics: Option<LyricsData>,
+  #[serde(default)]
+  track_structure_list: Option<Vec<LyricParagraph>>,
+  /// Missing when the song has no synced lyrics
+  #[serde(default)]
+  subtitle: Option<SubtitleData>,
+  /// Missing when the song has no word-synced lyrics
+  #[serde(default)]
+  richsync: Option<RichSyncData>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TrackData {
+  /// Numeric track ID
+  id: Option<u64>,
+  name: String,
+  artist_name: String,
+  album_name: Option<String>,
+  /// Like `Linkin-Park/Meteora`, missing for singles without an album page
+  #[serde(default)]
+  album_vanity_id: Option<String>,
+  has_lyrics: bool,
+  #[serde(default)]
+  has_track_structure: bool,
+  #[serde(default)]
+  artists: Vec<ArtistData>,
+  cover_image: Option<String>,
+  release_date: Option<String>,
+  spotify_id: Option<String>,
+  vanity_id: String,
+  /// Seconds
+  length: Option<u32>,
+  isrc: Option<String>,
+  #[serde(default, deserialize_with = "flag")]
+  explicit: bool,
+  #[serde(default, deserialize_with = "flag")]
+  instrumental: bool,
+  /// Lyrics can't be shown in the region of the request
+  #[serde(default, deserialize_with = "flag")]
+  restricted: bool,
+  #[serde(default)]
+  songwriters: Vec<NameData>,
+  #[serde(default)]
+  publishers: Vec<NameData>,
+}
+
+#[derive(Deserialize)]
+struct NameData {
+  name: String,
+}
+
+#[derive(Deserialize)]
+struct ArtistData {
+  #[serde(default)]
+  genres: Vec<GenreData>,
+}
+
+#[derive(Deserialize)]
+struct GenreData {
+  name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LyricsData {
+  /// Empty while the lyrics are waiting for review
+  #[serde(default)]
+  body: String,
+  #[serde(default, deserialize_with = "flag")]
+  restricted: bool,
+  copyright: Option<String>,
+  /// ISO 639-1 code
+  language: Option<String>,
+  language_description: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SubtitleData {
+  #[serde(deserialize_with = "embedded_json")]
+  subtitle_body: Vec<SubtitleLine>,
+}
+
+#[derive(Deserialize)]
+struct SubtitleLine {
+  text: String,
+  time: SubtitleTime,
+}
+
+#[derive(Deserialize)]
+struct SubtitleTime {
+  /// Seconds since the song start
+  total: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RichSyncData {
+  #[serde(deserialize_with = "embedded_json")]
+  richsync_body: Vec<RichSyncLineData>,
+}
+
+#[derive(Deserialize)]
+struct RichSyncLineData {
+  /// Line start, in seconds since the song start
+  ts: f64,
+  /// Line end, in seconds since the song start
+  te: f64,
+  /// Line words
+  l: Vec<RichSyncWordData>,
+}
+
+#[derive(Deserialize)]
+struct RichSyncWordData {
+  /// Word text, spaces are words too
+  c: String,
+  /// Offset from the line start, in seconds
+  o: f64,
+}
+
+/// Flags come as booleans or as `0`/`1`
+fn flag<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+  #[derive(Deserialize)]
+  #[serde(untagged)]
+  enum Flag {
+    Bool(bool),
+    Number(u64),
   }
+
+  Ok(match Flag::deserialize(deserializer)? {
+    Flag::Bool(b) => b,
+    Flag::Number(n) => n != 0,
+  })
 }
 
-pub struct MxmAPI {
-  tries: u32,
-  timeout: u32,
-  headers: Option<HeaderMap>,
+/// Some fields are json documents stored as a string
+fn embedded_json<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+  D: Deserializer<'de>,
+  T: serde::de::DeserializeOwned,
+{
+  let body = String::deserialize(deserializer)?;
+  serde_json::from_str(&body).map_err(de::Error::custom)
+}
+/// Parse the `__NEXT_DATA__` json of a track page, errors carry the path of the offending field
+fn parse_track(json: &str) -> Result<TrackInfo, serde_path_to_error::Error<serde_json::Error>> {
+  let mut de = serde_json::Deserializer::from_str(json);
+  let data: NextData<PageData> = serde_path_to_error::deserialize(&mut de)?;
+  let TrackInfoData {
+    track,
+    lyrics,
+    track_structure_list,
+    subtitle,
+    richsync,
+  } = data.props.page_props.data.track_info.data;
+  let lyrics_struct = track_structure_list.unwrap_or_default();
+
+  let synced = subtitle.map(|s| {
+    let mut lines: Vec<SyncedLine> = s
+      .subtitle_body
+      .into_iter()
+      .map(|l| SyncedLine {
+        time: Duration::from_secs_f64(l.time.total.max(0.0)),
+        text: l.text,
+        section: None,
+      })
+      .collect();
+    SyncedLine::link_sections(&mut lines, &lyrics_struct);
+    lines
+  });
+
+  let secs = |s: f64| Duration::from_secs_f64(s.max(0.0));
+  let rich_synced = richsync.map(|r| {
+    r.richsync_body
+      .into_iter()
+      .map(|l| RichSyncLine {
+        start: secs(l.ts),
+        end: secs(l.te),
+        words: l.l.into_iter().map(|w| (secs(w.o), w.c)).collect(),
+      })
+      .collect()
+  });
+
+  let status = lyrics_status(&track, lyrics.as_ref());
+  let genre = track
+    .artists
+    .into_iter()
+    .next()
+    .and_then(|a| a.genres.into_iter().next())
+    .map(|g| g.name);
+
+  Ok(TrackInfo {
+    name: track.name,
+    artist: track.artist_name,
+    album: track.album_name,
+    album_url: track
+      .album_vanity_id
+      .map(|v| format!("{}/album/{v}", link::MUSIXMATCH_URL)),
+    lyrics_status: status,
+    has_lyrics_struct: track.has_track_structure && !lyrics_struct.is_empty(),
+    lyrics_lang: lyrics.as_ref().and_then(|l| l.language_description.clone()),
+    lyrics_lang_code: lyrics
+      .as_ref()
+      .and_then(|l| l.language.clone())
+      .filter(|l| !l.is_empty()),
+    lyrics_copyright: lyrics.as_ref().and_then(|l| l.copyright.clone()),
+    lyrics: lyrics.map(|l| l.body).unwrap_or_default(),
+    lyrics_struct,
+    synced,
+    rich_synced,
+    genre,
+    cover: track.cover_image,
+    released: track
+      .release_date
+      .as_deref()
+      .and_then(|d| d.get(0..10))
+      .map(String::from),
+    duration: track.length.filter(|l| *l > 0),
+    isrc: track.isrc.filter(|i| !i.is_empty()),
+    explicit: track.explicit,
+    instrumental: track.instrumental,
+    writers: track.songwriters.into_iter().map(|n| n.name).collect(),
+    publishers: track.publishers.into_iter().map(|n| n.name).collect(),
+    track_id: track.id,
+    spotify: track
+      .spotify_id
+      .map(|s| format!("https://open.spotify.com/track/{s}")),
+    url: format!("{}/lyrics/{}", link::MUSIXMATCH_URL, track.vanity_id),
+    source: MxmAPI::NAME,
+  })
 }
 
-pub enum ResponseErr {
-  Captcha,
-  JsEnforcement,
-  NoEnoughData,
-  RequestErr,
+/// Lyrics missing from a page can be missing, pending, restricted or never
+/// exist (instrumental songs)
+fn lyrics_status(track: &TrackData, lyrics: Option<&LyricsData>) -> LyricsStatus {
+  let has_body = lyrics.is_some_and(|l| !l.body.trim().is_empty());
+  if track.instrumental {
+    LyricsStatus::Instrumental
+  } else if track.restricted || lyrics.is_some_and(|l| l.restricted) {
+    LyricsStatus::Restricted
+  } else if track.has_lyrics && has_body {
+    LyricsStatus::Available
+  } else if track.has_lyrics {
+    LyricsStatus::Pending
+  } else {
+    LyricsStatus::Missing
+  }
 }
 
-impl MxmAPI {
+/// Parse the `__NEXT_DATA__` json of an album page
+fn parse_album(json: &str) -> Result<AlbumInfo, serde_path_to_error::Error<serde_json::Error>> {
+  let mut de = serde_json::Deserializer::from_str(json);
+  let data: NextData<AlbumPageData> = serde_path_to_error::deserialize(&mut de)?;
+  let AlbumInfoData { album, tracks } = data.props.page_props.data.album_info.data;
+
+  Ok(AlbumInfo {
+    name: album.name,
+    artist: album.artist_name,
+    released: album
+      .release_date
+      .as_deref()
+      .and_then(|d| d.get(0..10))
+      .map(String::from),
+    url: format!("{}/album/{}", link::MUSIXMATCH_URL, album.vanity_id),
+    tracks: tracks
+      .into_iter()
+      .map(|t| {
+        TrackItem::new(
+          format!("{}/lyrics/{}", link::MUSIXMATCH_URL, t.vanity_id),
+          t.name,
+        )
+      })
+      .collect(),
+  })
+}
+
+/// Parse the `__NEXT_DATA__` json of a page of an artist albums, gives the
+/// artist name, the albums and how many pages there are
+fn parse_artist_page(json: &str) -> Result<(String, Vec<AlbumItem>, u32), serde_path_to_error::Error<serde_json::Error>> {
+  let mut de = serde_json::Deserializer::from_str(json);
+  let data: NextData<ArtistPageData> = serde_path_to_error::deserialize(&mut de)?;
+  let ArtistAlbumsData {
+    artist,
+    albums,
+    total_pages,
+  } = data.props.page_props.data.artist_albums.data;
+
+  let albums = albums
+    .into_iter()
+    .map(|a| AlbumItem {
+      name: a.name,
+      released: a
+        .release_date
+        .as_deref()
+        .and_then(|d| d.get(0..10))
+        .map(String::from),
+      url: format!("{}/album/{}", link::MUSIXMATCH_URL, a.vanity_id),
+    })
+    .collect();
+  Ok((artist.name, albums, total_pages.unwrap_or(1)))
+}
+
+/// Non-blocking Musixmatch client, for the bot and batch jobs. Use `MxmAPI`
+/// from synchronous code
+pub struct AsyncMxmAPI {
+  retry: RetryPolicy,
+  timeout: u32,
+  headers: Option<HeaderMap>,
+  engines: Vec<Box<dyn SearchBackend>>,
+  /// Where Musixmatch pages are requested from
+  base_url: String,
+  /// Used for every request, keeps connections alive between them. One
+  /// per proxy when there are proxies
+  clients: Vec<reqwest::Client>,
+  proxies: Option<Arc<ProxyPool>>,
+  /// Cookies of every client
+  jar: CookieJar,
+  /// Least time between the start of two requests
+  min_interval: Option<Duration>,
+  /// When the next request may start
+  next_slot: Mutex<Instant>,
+}
+
+impl AsyncMxmAPI {
   pub fn new(tries: u32, timeout: u32, headers: Option<HeaderMap>) -> Self {
-    Self {
+    let endpoints = Endpoints::default();
+    let retry = RetryPolicy {
       tries,
+      ..Default::default()
+    };
+    let jar = CookieJar::default();
+    Self {
+      clients: vec![http_client(&jar, &retry, None)],
+      retry,
       timeout,
       headers,
+      engines: search::ENGINES
+        .iter()
+        .filter_map(|n| search::by_name(n, &endpoints, &Default::default()).ok())
+        .collect(),
+      base_url: endpoints.musixmatch,
+      proxies: None,
+      jar,
+      min_interval: None,
+      next_slot: Mutex::new(Instant::now()),
     }
   }
 
-  pub fn get_from_url(&self, url: &String) -> TrackInfo {
-    let mut mxm_json: Option<String> = None;
-    let mut spinner = Spinner::new();
-    spinner.start("Getting song data".into());
+  /// Wait at least `interval` between the start of two requests, to not
+  /// overload the site on long jobs
+  pub(crate) fn rate_limit(mut self, interval: Duration) -> Self {
+    self.min_interval = Some(interval);
+    self
+  }
 
-    let mut headers = self.headers.clone().unwrap_or(HeaderMap::new());
-    headers.insert("cookies", reqwest::header::HeaderValue::from_static("").into());
+  /// Wait for the next free slot, concurrent calls get one each
+  async fn pace(&self) {
+    let Some(interval) = self.min_interval else {
+      return;
+    };
+    let slot = {
+      let mut next = self.next_slot.lock().unwrap();
+      let slot = (*next).max(Instant::now());
+      *next = slot + interval;
+      slot
+    };
+    tokio::time::sleep_until(slot).await;
+  }
 
-    for i in 1..=self.tries {
-      if let Ok(json_str) = get_json(&url, self.timeout, headers.clone()) {
-        mxm_json = Some(json_str);
-        break;
-      } else {
-        spinner.update(format!("Getting song data ({} try)", i + 1));
-      }
-    }
+  /// Keep cookies in the json file at `path`, loaded now and written by `save_cookies`
+  pub(crate) fn cookie_jar(mut self, path: PathBuf) -> Self {
+    self.jar = CookieJar::load(path);
+    self.rebuild_clients();
+    self
+  }
 
-    spinner.stop();
-    let Some(mxm_json) = mxm_json else {
-      macros::exit_err!("Couldn't get a (valid) response from the server");
-    };
+  /// Retry calls, and time them out, following `policy`
+  pub(crate) fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+    self.retry = policy;
+    self.rebuild_clients();
+    self
+  }
 
-    let Some(track) = TrackInfo::from(mxm_json) else {
-      macros::exit_err!("Couldn't read json from response");
-    };
+  /// Send every request through the proxies of `pool`
+  pub(crate) fn proxies(mut self, pool: Arc<ProxyPool>) -> Self {
+    self.proxies = Some(pool);
+    self.rebuild_clients();
+    self
+  }
 
-    track
+  /// Write the cookie jar to disk, if it has a file
+  pub(crate) fn save_cookies(&self) -> Result<(), String> {
+    self.jar.save()
   }
 
-  pub fn get_possible_links(&self, keyword: &String) -> Vec<TrackItem> {
-    let mut spinner = Spinner::new();
-    spinner.start("Getting url to musixmatch".into());
-
-    let mut urls_list: Option<Vec<TrackItem>> = None;
-    for i in 1..=self.tries {
-      match get_urls(&keyword, self.timeout, self.headers.clone()) {
-        Ok(val) => {
-          urls_list = Some(val);
-          break;
-        }
-        Err(ResponseErr::Captcha) => {
-          macros::exit_err!("Captcha triggered, page has no data");
-        }
-        Err(ResponseErr::JsEnforcement) => {
-          macros::exit_err!("JavaScript enforcement, page has no data");
-        }
-        _ => {
-          spinner.update(format!("Getting url to musixmatch ({} try)", i + 1));
+  /// Send the cookies of a Cookie `header` to the host of `url` only
+  pub(crate) fn add_cookies(&self, header: &str, url: &str) -> Result<(), String> {
+    self.jar.add_header(header, url)
+  }
+
+  fn rebuild_clients(&mut self) {
+    self.clients = match &self.proxies {
+      Some(pool) => pool
+        .urls()
+        .iter()
+        .map(|p| http_client(&self.jar, &self.retry, Some(p)))
+        .collect(),
+      None => vec![http_client(&self.jar, &self.retry, None)],
+    };
+  }
+
+  /// Retry `f` following the policy, each try with the client of the
+  /// proxy in use. A proxy that seems to be the problem is left for the
+  /// next one, which is tried even if the error is not retryable
+  async fn call<'a, T, F, Fut>(&'a self, f: F) -> Result<T, ResponseErr>
+  where
+    F: Fn(&'a reqwest::Client) -> Fut,
+    Fut: Future<Output = Result<T, ResponseErr>>,
+  {
+    self
+      .retry
+      .run(|| async {
+        self.pace().await;
+        let Some(pool) = &self.proxies else {
+          return f(&self.clients[0]).await;
+        };
+        let idx = pool.current();
+        match f(&self.clients[idx]).await {
+          Err(e) if proxy::blames_proxy(&e) && pool.mark_dead(idx) => Err(ResponseErr::ProxyFailed {
+            proxy: pool.urls()[idx].clone(),
+            source: Box::new(e),
+          }),
+          res => res,
         }
+      })
+      .await
+  }
+
+  /// Request Musixmatch pages from `url` instead of the public site
+  pub(crate) fn base_url(mut self, url: &str) -> Self {
+    self.base_url = url.trim_end_matches('/').to_string();
+    self
+  }
+
+  /// Search engines to use, in fallback order
+  pub(crate) fn search_engines(mut self, engines: Vec<Box<dyn SearchBackend>>) -> Self {
+    self.engines = engines;
+    self
+  }
+
+  pub async fn get_from_url(&self, url: &str) -> Result<TrackInfo, ResponseErr> {
+    let url = self.rebase(url).ok_or(ResponseErr::InvalidUrl(url.into()))?;
+    let json = self
+      .call(|client| get_json(client, &url, self.timeout, self.page_headers()))
+      .await?;
+    parse_track(&json).map_err(|e| ResponseErr::parse_with("track page data", e))
+  }
+
+  /// Get an album and its tracklist from its page at `url`
+  pub async fn get_album(&self, url: &str) -> Result<AlbumInfo, ResponseErr> {
+    let url = self
+      .rebase_album(url)
+      .ok_or(ResponseErr::InvalidUrl(url.into()))?;
+    let json = self
+      .call(|client| get_json(client, &url, self.timeout, self.page_headers()))
+      .await?;
+    parse_album(&json).map_err(|e| ResponseErr::parse_with("album page data", e))
+  }
+
+  /// Get an artist and their albums from the artist page at `url`, reading
+  /// up to `max_pages` pages of albums
+  pub async fn get_artist(&self, url: &str, max_pages: u32) -> Result<ArtistInfo, ResponseErr> {
+    let url = link::musixmatch(url, &self.base_url)
+      .filter(|l| l.kind == LinkKind::MxmArtist)
+      .ok_or(ResponseErr::InvalidUrl(url.into()))?
+      .url;
+
+    let mut artist = ArtistInfo {
+      name: String::new(),
+      url: format!("{}{}", link::MUSIXMATCH_URL, &url[self.base_url.len()..]),
+      albums: Vec::new(),
+    };
+    for page in 1..=max_pages {
+      let page_url = match page {
+        1 => format!("{url}/albums"),
+        n => format!("{url}/albums/{n}"),
+      };
+      let json = self
+        .call(|client| get_json(client, &page_url, self.timeout, self.page_headers()))
+        .await?;
+      let (name, albums, pages) = parse_artist_page(&json).map_err(|e| ResponseErr::parse_with("artist page data", e))?;
+
+      artist.name = name;
+      let done = albums.is_empty() || page >= pages;
+      artist.albums.extend(albums);
+      if done {
+        break;
       }
     }
+    Ok(artist)
+  }
 
-    spinner.stop();
-    let uti = urls_list.unwrap_or_else(|| {
-      macros::exit_err!("There are no results for this query");
-    });
+  /// Get the translation to `lang` (language code, like `es`) of the lyrics at `url`
+  pub async fn get_translation(&self, url: &str, lang: &str) -> Result<Translation, ResponseErr> {
+    let url = self.rebase(url).ok_or(ResponseErr::InvalidUrl(url.into()))?;
+    let url = format!("{}/translation/{}", url.trim_end_matches('/'), lang);
+    let json = self
+      .call(|client| get_json(client, &url, self.timeout, self.page_headers()))
+      .await?;
+    Translation::from_json(&json, lang).map_err(|e| ResponseErr::parse_with("translation page data", e))
+  }
 
-    uti
+  pub async fn get_possible_links(&self, keyword: &str) -> Result<Vec<TrackItem>, ResponseErr> {
+    self
+      .call(|client| {
+        get_urls(
+          client,
+          keyword,
+          self.timeout,
+          self.headers.clone(),
+          &self.engines,
+        )
+      })
+      .await
   }
-}
 
-pub fn get_urls(keyword: &String, timeout: u32, headers_map: Option<HeaderMap>) -> Result<Vec<TrackItem>, ResponseErr> {
-  let url = reqwest::Url::parse(
-    format!(
-      "https://www.google.com/search?q=site%3Amusixmatch.com%2Flyrics%20lyrics%20{}",
-      keyword
-    )
-    .as_str(),
-  )
-  .unwrap();
+  /// Fetch every page at once, results are in the same order as `urls`
+  pub async fn prefetch(&self, urls: &[&str]) -> Vec<Result<TrackInfo, ResponseErr>> {
+    futures::future::join_all(urls.iter().map(|url| self.get_from_url(url))).await
+  }
 
-  let client = reqwest::blocking::Client::new()
-    .get(url)
-    .timeout(Duration::from_millis(timeout as u64))
-    .headers(headers_map.unwrap_or(HeaderMap::new()));
+  /// Download any file (like a cover image) with the shared client and headers
+  pub async fn get_bytes(&self, url: &str) -> Result<Vec<u8>, ResponseErr> {
+    self
+      .call(|client| async move {
+        let response = client
+          .get(url)
+          .timeout(Duration::from_millis(self.timeout as u64))
+          .headers(self.headers.clone().unwrap_or_default())
+          .send()
+          .await?;
+        Ok(check_status(response)?.bytes().await?.to_vec())
+      })
+      .await
+  }
 
-  let res = client.send();
-  let response = match res {
-    Ok(data) => data.text().unwrap(),
-    Err(_) => return Err(ResponseErr::RequestErr),
-  };
+  fn page_headers(&self) -> HeaderMap {
+    let mut headers = self.headers.clone().unwrap_or_default();
+    headers.insert("cookies", reqwest::header::HeaderValue::from_static(""));
+    headers
+  }
 
-  if response.contains(r#"/httpservice/retry/enablejs"#) {
-    return Err(ResponseErr::JsEnforcement);
+  /// URL to fetch a lyrics page from, public Musixmatch URLs are moved to
+  /// the configured base URL. `None` if it is not a lyrics URL
+  fn rebase(&self, url: &str) -> Option<String> {
+    link::musixmatch(url, &self.base_url)
+      .filter(|l| matches!(l.kind, LinkKind::MxmTrack { .. }))
+      .map(|l| l.url)
   }
 
-  if response.contains(r#"<script src="https://www.google.com/recaptcha/api.js" async defer></script>"#) {
-    return Err(ResponseErr::Captcha);
+  /// Like `rebase`, for album pages
+  fn rebase_album(&self, url: &str) -> Option<String> {
+    link::musixmatch(url, &self.base_url)
+      .filter(|l| l.kind == LinkKind::MxmAlbum)
+      .map(|l| l.url)
   }
+}
 
-  let (url_list, url_desc) = parse_search_results(&response)?;
+/// Blocking wrapper around `AsyncMxmAPI`, runs each call to completion on
+/// its own single threaded runtime and shows a spinner meanwhile. Results
+/// are kept in the cache, if there is one
+pub struct MxmAPI {
+  inner: AsyncMxmAPI,
+  runtime: tokio::runtime::Runtime,
+  /// Pages fetched by `prefetch`, taken by the next fetch of the same URL
+  prefetched: RefCell<HashMap<String, Result<TrackInfo, ResponseErr>>>,
+  cache: Option<Cache>,
+}
 
-  let mut urls_tp: Vec<TrackItem> = Vec::new();
+impl MxmAPI {
+  pub(crate) const NAME: &'static str = "musixmatch";
 
-  // Add all not translation URLs
-  for i in 0..url_list.len() {
-    if let Some(tpos) = url_list[i].find("/translation/") {
-      let uns_url = &url_list[i][0..tpos];
-      if url_list.contains(&uns_url) {
-        continue;
-      }
-      urls_tp.push(TrackItem::new(uns_url.to_string(), url_desc[i].to_string()))
-    } else {
-      urls_tp.push(TrackItem::new(
-        String::from(url_list[i]),
-        String::from(url_desc[i]),
-      ))
+  pub fn new(tries: u32, timeout: u32, headers: Option<HeaderMap>) -> Self {
+    Self {
+      inner: AsyncMxmAPI::new(tries, timeout, headers),
+      runtime: tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("build the tokio runtime"),
+      prefetched: RefCell::new(HashMap::new()),
+      cache: None,
     }
   }
 
-  Ok(urls_tp)
-}
+  /// Keep song data, searches and covers in `cache`
+  pub(crate) fn cache(mut self, cache: Cache) -> Self {
+    self.cache = Some(cache);
+    self
+  }
 
-fn get_json(url: &String, timeout: u32, headers_map: HeaderMap) -> Result<String, String> {
-  if !is_musixmatch_url(&url) {
-    return Err("Invalid URL".into());
+  /// Request Musixmatch pages from `url` instead of the public site
+  pub(crate) fn base_url(mut self, url: &str) -> Self {
+    self.inner = self.inner.base_url(url);
+    self
   }
 
-  let client = reqwest::blocking::Client::builder()
-    .gzip(true)
-    .build()
-    .unwrap_or_else(|e| {
-      macros::exit_err!("Could not create a client: {e}");
-    });
+  /// Search engines to use, in fallback order
+  pub(crate) fn search_engines(mut self, engines: Vec<Box<dyn SearchBackend>>) -> Self {
+    self.inner = self.inner.search_engines(engines);
+    self
+  }
 
-  let response = client
-    .get(url)
-    .timeout(Duration::from_millis(timeout as u64))
-    .headers(headers_map)
-    .send()
-    .unwrap_or_else(|e| {
-      macros::exit_err!("Could send request: {e}");
-    });
+  /// Retry calls, and time them out, following `policy`
+  pub(crate) fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+    self.inner = self.inner.retry_policy(policy);
+    self
+  }
 
-  let html_str = response.text().unwrap_or_else(|e| {
-    macros::exit_err!("Could not get response content: {e}");
-  });
+  /// Keep cookies in the json file at `path` between runs
+  pub(crate) fn cookie_jar(mut self, path: PathBuf) -> Self {
+    self.inner = self.inner.cookie_jar(path);
+    self
+  }
 
-  let json_str = extract_between(
-    &html_str,
-    r#"<script id="__NEXT_DATA__" type="application/json">"#,
-    r#"</script>"#,
-  );
+  /// Send every request through the proxies of `pool`
+  pub(crate) fn proxies(mut self, pool: Arc<ProxyPool>) -> Self {
+    self.inner = self.inner.proxies(pool);
+    self
+  }
 
-  println!("{json_str:#?}");
+  /// Send the cookies of a Cookie `header` to the host of `url` only
+  pub(crate) fn add_cookies(&self, header: &str, url: &str) -> Result<(), String> {
+    self.inner.add_cookies(header, url)
+  }
 
-  if let Some(v) = json_str {
-    return Ok(v.into());
+  /// Wait at least `interval` between the start of two requests
+  pub(crate) fn rate_limit(mut self, interval: Duration) -> Self {
+    self.inner = self.inner.rate_limit(interval);
+    self
   }
 
-  return Err("Invalid response structure".into());
-}
+  pub fn get_from_url(&self, url: &str) -> Result<TrackInfo, ResponseErr> {
+    if let Some(res) = self.prefetched.borrow_mut().remove(url) {
+      return res;
+    }
+    let key = self.page_key(url);
+    if let Some(track) = self.cached(EntryKind::Track, &key) {
+      return Ok(track);
+    }
+    self.check_online()?;
 
-fn extract_between<'a>(text: &'a str, start: &str, end: &str) -> Option<&'a str> {
-  let start_pos = text.find(start)? + start.len();
-  println!("\n{}", &text[start_pos - start.len()..start_pos]);
-  let end_pos = text[start_pos..].find(end)?;
-  Some(&text[start_pos..start_pos + end_pos])
-}
+    let res = self.block_on("Getting song data", self.inner.get_from_url(url));
+    if let Ok(track) = &res {
+      self.store(EntryKind::Track, &key, track);
+    }
+    res
+  }
 
-fn parse_search_results(response: &str) -> Result<(Vec<&str>, Vec<&str>), ResponseErr> {
-  if !response.contains(r#"<a jsname="UWckNb" class="VfSr4c" href=""#) {
-    return Err(ResponseErr::NoEnoughData);
+  pub(crate) fn get_album(&self, url: &str) -> Result<AlbumInfo, ResponseErr> {
+    let key = self.page_key(url);
+    if let Some(album) = self.cached(EntryKind::Album, &key) {
+      return Ok(album);
+    }
+    self.check_online()?;
+
+    let res = self.block_on("Getting album tracklist", self.inner.get_album(url));
+    if let Ok(album) = &res {
+      self.store(EntryKind::Album, &key, album);
+    }
+    res
   }
 
-  let mut url_list = Vec::new();
-  let mut search_pos = 0;
+  pub(crate) fn get_artist(&self, url: &str, max_pages: u32) -> Result<ArtistInfo, ResponseErr> {
+    // A shorter read is not the whole list of a longer one
+    let key = format!("{}?max_pages={max_pages}", self.page_key(url));
+    if let Some(artist) = self.cached(EntryKind::Artist, &key) {
+      return Ok(artist);
+    }
+    self.check_online()?;
 
-  while let Some(link_start) = response[search_pos..].find(r#"<a jsname="UWckNb" class="VfSr4c" href=""#) {
-    let absolute_pos = search_pos + link_start;
-    if let Some(url) = extract_between(&response[absolute_pos..], r#"href=""#, r#"""#) {
-      url_list.push(url);
+    let res = self.block_on("Getting artist albums", self.inner.get_artist(url, max_pages));
+    if let Ok(artist) = &res {
+      self.store(EntryKind::Artist, &key, artist);
     }
-    search_pos = absolute_pos + 1;
+    res
   }
 
-  if url_list.is_empty() {
-    return Err(ResponseErr::NoEnoughData);
+  /// Get the translation to `lang` (language code, like `es`) of the lyrics at `url`
+  pub fn get_translation(&self, url: &str, lang: &str) -> Result<Translation, ResponseErr> {
+    self.check_online()?;
+    self.block_on("Getting translation", self.inner.get_translation(url, lang))
   }
 
-  let mut url_desc = Vec::new();
+  pub fn get_possible_links(&self, keyword: &str) -> Result<Vec<TrackItem>, ResponseErr> {
+    let key = cache::normalize_query(keyword);
+    if let Some(items) = self.cached(EntryKind::Search, &key) {
+      return Ok(items);
+    }
+    self.check_online()?;
+
+    let res = self.block_on(
+      "Getting url to musixmatch",
+      self.inner.get_possible_links(keyword),
+    );
+    if let Ok(items) = &res {
+      self.store(EntryKind::Search, &key, items);
+    }
+    res
+  }
 
-  search_pos = 0;
+  /// Fetch every candidate concurrently (the cached ones are not), tell
+  /// whether each one could be parsed and has lyrics. The pages are kept
+  /// for the next `get_from_url`
+  pub(crate) fn prefetch(&self, items: &[TrackItem]) -> Vec<Result<bool, String>> {
+    let mut prefetched = self.prefetched.borrow_mut();
+    let mut missing = Vec::new();
+    for item in items {
+      match self.cached(EntryKind::Track, &self.page_key(&item.url)) {
+        Some(track) => {
+          prefetched.insert(item.url.clone(), Ok(track));
+        }
+        None => missing.push(item.url.as_str()),
+      }
+    }
 
-  while let Some(desc_start) = response[search_pos..].find(r#"<br><h3 class="LC20lb MBeuO DKV0Md">"#) {
-    let absolute_pos = search_pos + desc_start;
-    if let Some(desc) = extract_between(
-      &response[absolute_pos..],
-      r#"<h3 class="LC20lb MBeuO DKV0Md">"#,
-      "<",
-    ) {
-      url_desc.push(desc);
+    if self.check_online().is_ok() {
+      let results = self.block_on("Getting candidate pages", self.inner.prefetch(&missing));
+      for (url, res) in missing.into_iter().zip(results) {
+        if let Ok(track) = &res {
+          self.store(EntryKind::Track, &self.page_key(url), track);
+        }
+        prefetched.insert(url.into(), res);
+      }
     }
-    search_pos = absolute_pos + 1;
+
+    items
+      .iter()
+      .map(|item| match prefetched.get(&item.url) {
+        Some(Ok(track)) => Ok(track.has_lyrics()),
+        Some(Err(e)) => Err(e.to_string()),
+        None => Err(ResponseErr::NotCached.to_string()),
+      })
+      .collect()
   }
 
-  Ok((url_list, url_desc))
+  /// Download any file (like a cover image) with the shared client and headers
+  pub fn get_bytes(&self, url: &str) -> Result<Vec<u8>, ResponseErr> {
+    let cache = self.cache.as_ref();
+    if let Some(bytes) = cache.and_then(|c| c.get_bytes(EntryKind::Cover, url)) {
+      return Ok(bytes);
+    }
+    self.check_online()?;
+
+    let res = self.block_on("Getting cover art", self.inner.get_bytes(url));
+    if let (Some(cache), Ok(bytes)) = (cache, &res) {
+      if let Err(e) = cache.put_bytes(EntryKind::Cover, url, bytes) {
+        macros::log_err!("{e}");
+      }
+    }
+    res
+  }
+
+  /// Cache key of a song or album page, the same for every form of its
+  /// URL and whatever the configured base is
+  fn page_key(&self, url: &str) -> String {
+    let base = self.inner.base_url.trim_end_matches('/');
+    let url = match link::musixmatch(url, base) {
+      Some(l) => format!(
+        "{}{}",
+        link::MUSIXMATCH_URL,
+        l.url.strip_prefix(base).unwrap_or(&l.url)
+      ),
+      None => url.into(),
+    };
+    cache::normalize_url(&url)
+  }
+
+  fn cached<T: DeserializeOwned>(&self, kind: EntryKind, key: &str) -> Option<T> {
+    self.cache.as_ref()?.get(kind, key)
+  }
+
+  fn store<T: Serialize>(&self, kind: EntryKind, key: &str, value: &T) {
+    if let Some(Err(e)) = self.cache.as_ref().map(|c| c.put(kind, key, value)) {
+      macros::log_err!("{e}");
+    }
+  }
+
+  /// Offline mode allows no requests, only cached data
+  fn check_online(&self) -> Result<(), ResponseErr> {
+    match &self.cache {
+      Some(c) if c.mode == CacheMode::Offline => Err(ResponseErr::NotCached),
+      _ => Ok(()),
+    }
+  }
+
+  /// Run `fut` to completion, cookies it got are saved right after
+  fn block_on<T>(&self, hint: &str, fut: impl Future<Output = T>) -> T {
+    let mut spinner = Spinner::new();
+    spinner.start(hint.into());
+    let res = self.runtime.block_on(fut);
+    spinner.stop();
+
+    if let Err(e) = self.inner.save_cookies() {
+      macros::log_err!("{e}");
+    }
+    res
+  }
 }
 
-pub(crate) fn is_musixmatch_url(url: &str) -> bool {
-  let mut idx = 0;
+impl LyricsProvider for MxmAPI {
+  fn name(&self) -> &'static str {
+    Self::NAME
+  }
 
-  if !url[idx..].starts_with("http") {
-    return false;
+  fn handles_url(&self, url: &str) -> bool {
+    self.inner.rebase(url).is_some()
   }
-  idx += 4;
 
-  if url[idx..].starts_with("s") {
-    idx += 1;
+  fn search(&self, query: &str) -> Result<Vec<TrackItem>, ResponseErr> {
+    self.get_possible_links(query)
   }
 
-  if !url[idx..].starts_with("://") {
-    return false;
+  fn fetch(&self, url: &str) -> Result<TrackInfo, ResponseErr> {
+    self.get_from_url(url)
   }
-  idx += 3;
+}
 
-  if url[idx..].starts_with("www.") {
-    idx += 4;
+/// Search with every engine in order, until one gives results. Engines
+/// that block the request (or find nothing) are skipped
+pub(crate) async fn get_urls(
+  client: &reqwest::Client,
+  keyword: &str,
+  timeout: u32,
+  headers_map: Option<HeaderMap>,
+  engines: &[Box<dyn SearchBackend>],
+) -> Result<Vec<TrackItem>, ResponseErr> {
+  let mut blocked: Option<ResponseErr> = None;
+  let mut failed: Option<ResponseErr> = None;
+  let mut found = None;
+
+  let headers = headers_map.unwrap_or_default();
+  for engine in engines {
+    match search_with(client, engine.as_ref(), keyword, timeout, &headers).await {
+      Ok(urls) => {
+        found = Some(urls);
+        break;
+      }
+      Err(ResponseErr::NoEnoughData) => (),
+      Err(
+        e @ (ResponseErr::Captcha
+        | ResponseErr::JsEnforcement
+        | ResponseErr::Consent
+        | ResponseErr::Interstitial
+        | ResponseErr::Blocked),
+      ) => {
+        macros::log_inf!("{} search failed: {e}", engine.name());
+        blocked.get_or_insert(e);
+      }
+      // Network and HTTP errors of one engine say nothing of the others
+      Err(e) => {
+        macros::log_inf!("{} search failed: {e}", engine.name());
+        failed.get_or_insert(e);
+      }
+    }
   }
 
-  if !url[idx..].starts_with("musixmatch.com/lyrics/") {
-    return false;
+  // A blocked engine explains the failure best (and blames the proxy in use
+  // with it), an empty one is retried as if results could show up
+  let Some(url_list) = found else {
+    return Err(blocked.or(failed).unwrap_or(ResponseErr::NoEnoughData));
+  };
+
+  let mut urls_tp: Vec<TrackItem> = Vec::new();
+
+  // Translation URLs are folded into their track URL, keeping the language
+  for (url, desc) in url_list.iter() {
+    let Some(Link {
+      kind: LinkKind::MxmTrack { translation },
+      url,
+    }) = link::musixmatch(url, link::MUSIXMATCH_URL)
+    else {
+      continue;
+    };
+
+    let pos = match urls_tp.iter().position(|t| t.url == url) {
+      Some(pos) => pos,
+      None => {
+        urls_tp.push(TrackItem::new(url, desc.clone()));
+        urls_tp.len() - 1
+      }
+    };
+
+    if let Some(lang) = translation {
+      if !urls_tp[pos].translations.contains(&lang) {
+        urls_tp[pos].translations.push(lang);
+      }
+    }
   }
-  idx += 22;
 
-  url[idx..].len() > 0
+  Ok(urls_tp)
+}
+
+/// Results of `engine`, giving the cookie consent it asks for first (once,
+/// its cookies are kept in the jar)
+async fn search_with(
+  client: &reqwest::Client,
+  engine: &dyn SearchBackend,
+  keyword: &str,
+  timeout: u32,
+  headers: &HeaderMap,
+) -> Result<Vec<(String, String)>, ResponseErr> {
+  let url = engine.url(keyword)?;
+  let (page_url, page) = get_page(client, url.clone(), timeout, headers).await?;
+  match (engine.parse(&page), engine.consent_form(&page)) {
+    (Err(ResponseErr::Consent), Some(form)) => {
+      give_consent(client, &page_url, form, timeout, headers).await?;
+      let (_, page) = get_page(client, url, timeout, headers).await?;
+      engine.parse(&page)
+    }
+    (res, _) => res,
+  }
+}
+
+/// A search results page and its URL after redirects. Google `sorry`
+/// pages come with a 429 status, they are told apart from rate limits
+async fn get_page(client: &reqwest::Client, url: Url, timeout: u32, headers: &HeaderMap) -> Result<(Url, String), ResponseErr> {
+  let response = client
+    .get(url)
+    .timeout(Duration::from_millis(timeout as u64))
+    .headers(headers.clone())
+    .send()
+    .await?;
+  if response.url().path().starts_with("/sorry/") {
+    return Err(ResponseErr::Blocked);
+  }
+  let page_url = response.url().clone();
+  Ok((page_url, check_status(response)?.text().await?))
+}
+
+/// Send a consent form of the page at `page_url`, only to the same site
+/// or the Google consent one
+async fn give_consent(
+  client: &reqwest::Client,
+  page_url: &Url,
+  form: ConsentForm,
+  timeout: u32,
+  headers: &HeaderMap,
+) -> Result<(), ResponseErr> {
+  let action = page_url
+    .join(&form.action)
+    .map_err(|_| ResponseErr::InvalidUrl(form.action.clone()))?;
+  let trusted = action.host_str() == page_url.host_str()
+    || action
+      .host_str()
+      .is_some_and(|h| is_consent_host(h, page_url.host_str().unwrap_or_default()));
+  if !trusted {
+    return Err(ResponseErr::Consent);
+  }
+
+  macros::log_inf!("Giving the cookie consent Google asked for");
+  let response = client
+    .post(action)
+    .timeout(Duration::from_millis(timeout as u64))
+    .headers(headers.clone())
+    .form(&form.fields)
+    .send()
+    .await?;
+  check_status(response)?;
+  Ok(())
+}
+
+/// Google consent site, `consent.google.com` or the one of the country
+/// site searched (like `consent.google.de` for `www.google.de`)
+fn is_consent_host(host: &str, page_host: &str) -> bool {
+  if host == "consent.google.com" {
+    return true;
+  }
+  let site = page_host.strip_prefix("www.").unwrap_or(page_host);
+  site.starts_with("google.") && host.strip_prefix("consent.") == Some(site)
+}
+
+async fn get_json(client: &reqwest::Client, url: &str, timeout: u32, headers_map: HeaderMap) -> Result<String, ResponseErr> {
+  let html_str = client
+    .get(url)
+    .timeout(Duration::from_millis(timeout as u64))
+    .headers(headers_map)
+    .send()
+    .await?;
+  let html_str = check_status(html_str)?.text().await?;
+
+  let json_str = extract_between(
+    &html_str,
+    r#"<script id="__NEXT_DATA__" type="application/json">"#,
+    r#"</script>"#,
+  );
+
+  json_str
+    .map(String::from)
+    .ok_or(ResponseErr::parse("missing __NEXT_DATA__ script"))
+}
+
+/// Client with gzip support, the policy connect and read timeouts, the
+/// jar cookies, going through `proxy` if given
+fn http_client(jar: &CookieJar, policy: &RetryPolicy, proxy: Option<&str>) -> reqwest::Client {
+  let builder = reqwest::Client::builder()
+    .gzip(true)
+    .connect_timeout(Duration::from_millis(policy.connect_timeout))
+    .read_timeout(Duration::from_millis(policy.read_timeout))
+    .cookie_provider(jar.store());
+  let builder = match proxy {
+    // Already validated by the pool
+    Some(p) => builder.proxy(reqwest::Proxy::all(p).expect("valid proxy URL")),
+    None => builder,
+  };
+  builder.build().expect("build the HTTP client")
+}
+
+pub(crate) fn extract_between<'a>(text: &'a str, start: &str, end: &str) -> Option<&'a str> {
+  let start_pos = text.find(start)? + start.len();
+  let end_pos = text[start_pos..].find(end)?;
+  Some(&text[start_pos..start_pos + end_pos])
 }
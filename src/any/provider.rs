@@ -0,0 +1,141 @@
+use std::rc::Rc;
+
+use crate::any::{
+  error::ResponseErr,
+  macros, rank,
+  track::{TrackInfo, TrackItem, TrackQuery},
+};
+
+/// Candidates fetched per provider to find one matching the query fields
+const MAX_CHECKED: usize = 5;
+
+/// A source of lyrics, every provider gives the same `TrackInfo`
+pub(crate) trait LyricsProvider {
+  /// Short name used to select the provider (like `musixmatch`)
+  fn name(&self) -> &'static str;
+
+  /// Whether `url` is a song page this provider can fetch
+  fn handles_url(&self, url: &str) -> bool;
+
+  /// Find song pages for a free-form query, in the order the site gives them
+  fn search(&self, query: &str) -> Result<Vec<TrackItem>, ResponseErr>;
+
+  /// Find song pages by metadata, as free text unless the provider can
+  /// search by field
+  fn search_fields(&self, query: &TrackQuery) -> Result<Vec<TrackItem>, ResponseErr> {
+    self.search(&query.text())
+  }
+
+  /// Get the song data (and lyrics) from a song page URL
+  fn fetch(&self, url: &str) -> Result<TrackInfo, ResponseErr>;
+}
+
+/// Callback to choose one of the search results of a provider
+pub(crate) type Picker<'a> = dyn FnMut(&dyn LyricsProvider, &[TrackItem]) -> Option<usize> + 'a;
+
+/// Providers in priority order, when one has no lyrics (or fails) the next one is tried
+pub(crate) struct Providers {
+  chain: Vec<Rc<dyn LyricsProvider>>,
+}
+
+impl Providers {
+  pub(crate) fn new() -> Self {
+    Self { chain: Vec::new() }
+  }
+
+  pub(crate) fn add(mut self, provider: Rc<dyn LyricsProvider>) -> Self {
+    self.chain.push(provider);
+    self
+  }
+
+  /// Keep only the providers named in `names`, in that order
+  pub(crate) fn prioritize(mut self, names: &[String]) -> Result<Self, String> {
+    let mut chain = Vec::new();
+    for name in names {
+      let Some(pos) = self.chain.iter().position(|p| p.name() == name) else {
+        return Err(format!("Unknown provider '{name}'"));
+      };
+      chain.push(self.chain.remove(pos));
+    }
+    self.chain = chain;
+    Ok(self)
+  }
+
+  /// Fetch a song page URL with the first provider that can handle it
+  pub(crate) fn fetch_url(&self, url: &str) -> Option<Result<TrackInfo, ResponseErr>> {
+    let provider = self.chain.iter().find(|p| p.handles_url(url))?;
+    Some(provider.fetch(url))
+  }
+
+  /// Search and fetch through the chain until a provider has lyrics. If none
+  /// has them, the first song found is returned anyway (for its metadata)
+  pub(crate) fn find(&self, query: &str, pick: &mut Picker) -> Result<TrackInfo, ResponseErr> {
+    let mut no_lyrics: Option<TrackInfo> = None;
+    let mut last_err = ResponseErr::NoEnoughData;
+
+    for provider in self.chain.iter() {
+      let res = provider.search(query).and_then(|mut items| {
+        rank::rank(query, &mut items);
+        let idx = pick(provider.as_ref(), &items).ok_or(ResponseErr::NoEnoughData)?;
+        let item = items.get(idx).ok_or(ResponseErr::NoEnoughData)?;
+        provider.fetch(&item.url)
+      });
+
+      match res {
+        Ok(track) if track.has_lyrics() => return Ok(track),
+        Ok(track) => {
+          macros::log_inf!("{}: {}", provider.name(), track.lyrics_status.message());
+          no_lyrics.get_or_insert(track);
+        }
+        Err(e) => {
+          macros::log_err!("{} failed: {e}", provider.name());
+          last_err = e;
+        }
+      }
+    }
+
+    no_lyrics.ok_or(last_err)
+  }
+
+  /// Like `find`, searching by fields and taking only a song that matches
+  /// them all, candidates are checked best first
+  pub(crate) fn find_exact(&self, query: &TrackQuery) -> Result<TrackInfo, ResponseErr> {
+    let mut no_lyrics: Option<TrackInfo> = None;
+    let mut last_err = ResponseErr::NoMatch;
+    let text = query.text();
+
+    for provider in self.chain.iter() {
+      let mut items = match provider.search_fields(query) {
+        Ok(items) => items,
+        Err(e) => {
+          macros::log_err!("{} failed: {e}", provider.name());
+          last_err = e;
+          continue;
+        }
+      };
+      rank::rank(&text, &mut items);
+
+      for item in items.iter().take(MAX_CHECKED) {
+        match provider.fetch(&item.url) {
+          Ok(track) => match query.mismatch(&track) {
+            Some(why) => {
+              macros::log_inf!("{}: skipped {}, {why}", provider.name(), item.url);
+            }
+            None if track.has_lyrics() => return Ok(track),
+            None => {
+              macros::log_inf!("{}: {}", provider.name(), track.lyrics_status.message());
+              no_lyrics.get_or_insert(track);
+              break;
+            }
+          },
+          Err(e) => {
+            macros::log_err!("{} failed: {e}", provider.name());
+            last_err = e;
+          }
+        }
+      }
+    }
+
+    no_lyrics.ok_or(last_err)
+  }
+}
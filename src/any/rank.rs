@@ -0,0 +1,133 @@
+use crate::any::track::TrackItem;
+
+/// Words of other versions of a song, only wanted when the query has them
+const VERSION_WORDS: [&str; 4] = ["live", "remix", "karaoke", "cover"];
+/// Taken from the score for each version word only one of the query and
+/// the result has
+const VERSION_PENALTY: f64 = 0.3;
+/// Words less similar than this are taken as different words
+const MIN_WORD_SIMILARITY: f64 = 0.8;
+
+/// Artist and title of a search result, as far as its URL or description tell
+pub(crate) struct Candidate {
+  pub(crate) artist: String,
+  pub(crate) title: String,
+}
+
+impl Candidate {
+  /// From the URL slug when it has both (like `/lyrics/<artist>/<title>`),
+  /// or the description, like `Artist - Title Lyrics | Musixmatch` or
+  /// `Title by Artist`
+  pub(crate) fn parse(item: &TrackItem) -> Self {
+    if let Some(candidate) = Self::from_slug(&item.url) {
+      return candidate;
+    }
+
+    let desc = item.desc.replace('\u{a0}', " ");
+    let desc = desc.split(" | ").next().unwrap_or_default().trim();
+    let desc = strip_suffix_ignore_case(desc, " lyrics");
+    if let Some((title, artist)) = desc.rsplit_once(" by ") {
+      return Self::new(artist, title);
+    }
+    match desc.split_once(" - ") {
+      Some((artist, title)) => Self::new(artist, title),
+      None => Self::new("", desc),
+    }
+  }
+
+  fn from_slug(url: &str) -> Option<Self> {
+    let path = url.split(['?', '#']).next()?;
+    let mut segments = path.split('/').skip_while(|s| *s != "lyrics").skip(1);
+    let (artist, title) = (segments.next()?, segments.next()?);
+    (!artist.is_empty() && !title.is_empty()).then(|| Self::new(&artist.replace('-', " "), &title.replace('-', " ")))
+  }
+
+  fn new(artist: &str, title: &str) -> Self {
+    Self {
+      artist: artist.trim().into(),
+      title: title.trim().into(),
+    }
+  }
+}
+
+/// Sort `items` best match of `query` first, keeping the site order between
+/// equal scores, and set their score
+pub(crate) fn rank(query: &str, items: &mut [TrackItem]) {
+  for item in items.iter_mut() {
+    item.score = Some(score(query, item));
+  }
+  items.sort_by(|a, b| {
+    b.score
+      .unwrap_or_default()
+      .total_cmp(&a.score.unwrap_or_default())
+  });
+}
+
+/// From 0 to 1, half for how much of the query is in the artist and title,
+/// half for how much of the title is in the query (so `Numb` beats
+/// `Numb / Encore` for `numb`), less a penalty for each version (live,
+/// remix...) asked for but not given, or given but not asked for
+pub(crate) fn score(query: &str, item: &TrackItem) -> f64 {
+  let query = words(query);
+  let candidate = Candidate::parse(item);
+  let artist = words(&candidate.artist);
+  let title = words(&candidate.title);
+  if query.is_empty() || title.is_empty() {
+    return 0.0;
+  }
+
+  let both: Vec<String> = title.iter().chain(&artist).cloned().collect();
+  let query_found = coverage(&query, &both);
+  let title_found = coverage(&title, &query);
+
+  // The description may tell the version when the title does not, like
+  // `(Live)` after an LRCLIB title
+  let described = words(&item.desc);
+  let penalty = VERSION_WORDS
+    .iter()
+    .filter(|w| {
+      let asked = query.iter().any(|q| q == *w);
+      let given = title
+        .iter()
+        .chain(&described)
+        .filter(|d| !artist.contains(d))
+        .any(|d| d == *w);
+      asked != given
+    })
+    .count() as f64
+    * VERSION_PENALTY;
+
+  ((query_found + title_found) / 2.0 - penalty).max(0.0)
+}
+
+/// Average of how similar each word of `of` is to its closest one in `among`
+fn coverage(of: &[String], among: &[String]) -> f64 {
+  let total: f64 = of
+    .iter()
+    .map(|w| {
+      among
+        .iter()
+        .map(|a| strsim::jaro_winkler(w, a))
+        .filter(|s| *s >= MIN_WORD_SIMILARITY)
+        .fold(0.0, f64::max)
+    })
+    .sum();
+  total / of.len() as f64
+}
+
+/// Lowercase words, punctuation dropped
+pub(crate) fn words(text: &str) -> Vec<String> {
+  text
+    .split(|c: char| !c.is_alphanumeric())
+    .filter(|w| !w.is_empty())
+    .map(str::to_lowercase)
+    .collect()
+}
+
+fn strip_suffix_ignore_case<'a>(text: &'a str, suffix: &str) -> &'a str {
+  let at = text.len().saturating_sub(suffix.len());
+  match text.get(at..) {
+    Some(end) if end.eq_ignore_ascii_case(suffix) => &text[..at],
+    _ => text,
+  }
+}
@@ -0,0 +1,111 @@
+use rand::Rng;
+use reqwest::{header::RETRY_AFTER, Response, StatusCode};
+use serde::Deserialize;
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::any::error::ResponseErr;
+
+/// How calls are retried and how long they may take, times in milliseconds
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub(crate) struct RetryPolicy {
+  /// Attempts per call, the first one included
+  pub(crate) tries: u32,
+  /// Wait before the second try, doubled after each failure
+  pub(crate) backoff: u64,
+  /// Longest wait between two tries
+  pub(crate) max_backoff: u64,
+  /// Longest `Retry-After` wait accepted, longer ones fail the call
+  pub(crate) max_retry_after: u64,
+  /// Time to establish a connection
+  pub(crate) connect_timeout: u64,
+  /// Time between two reads of the response body
+  pub(crate) read_timeout: u64,
+  /// Time for a whole call, tries and waits included
+  pub(crate) deadline: Option<u64>,
+}
+
+impl Default for RetryPolicy {
+  fn default() -> Self {
+    Self {
+      tries: 5,
+      backoff: 250,
+      max_backoff: 8000,
+      max_retry_after: 60000,
+      connect_timeout: 3000,
+      read_timeout: 5000,
+      deadline: None,
+    }
+  }
+}
+
+impl RetryPolicy {
+  /// Wait before try number `next` (starting at 2), with jitter so
+  /// concurrent calls don't hit the server at the same time
+  fn delay(&self, next: u32) -> Duration {
+    let exp = self.backoff.saturating_mul(1 << (next - 2).min(16));
+    let max = exp.min(self.max_backoff);
+    Duration::from_millis(rand::thread_rng().gen_range(max / 2..=max))
+  }
+
+  /// Run `f` until it succeeds, fails with an error that is not worth
+  /// retrying, tries run out or the deadline passes
+  pub(crate) async fn run<T, F, Fut>(&self, f: F) -> Result<T, ResponseErr>
+  where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, ResponseErr>>,
+  {
+    let deadline = self
+      .deadline
+      .map(|ms| Instant::now() + Duration::from_millis(ms));
+
+    let mut res = self.attempt(&f, deadline).await;
+    for next in 2..=self.tries {
+      let wait = match res {
+        // Not worth blocking the run for, the error tells how long to wait
+        Err(ResponseErr::RateLimited(Some(after))) if after > Duration::from_millis(self.max_retry_after) => break,
+        Err(ResponseErr::RateLimited(Some(after))) => after.max(self.delay(next)),
+        Err(ref e) if e.is_retryable() => self.delay(next),
+        _ => break,
+      };
+      if deadline.is_some_and(|d| Instant::now() + wait >= d) {
+        break;
+      }
+
+      tokio::time::sleep(wait).await;
+      res = self.attempt(&f, deadline).await;
+    }
+    res
+  }
+
+  async fn attempt<T, F, Fut>(&self, f: &F, deadline: Option<Instant>) -> Result<T, ResponseErr>
+  where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, ResponseErr>>,
+  {
+    match deadline {
+      Some(d) => tokio::time::timeout_at(d, f())
+        .await
+        .unwrap_or(Err(ResponseErr::DeadlineExceeded)),
+      None => f().await,
+    }
+  }
+}
+
+/// Turn error statuses into errors, `429` ones keep the `Retry-After` wait
+pub(crate) fn check_status(response: Response) -> Result<Response, ResponseErr> {
+  if response.status() != StatusCode::TOO_MANY_REQUESTS {
+    return Ok(response.error_for_status()?);
+  }
+
+  // Only the delay in seconds form, HTTP dates fall back to the backoff
+  let after = response
+    .headers()
+    .get(RETRY_AFTER)
+    .and_then(|v| v.to_str().ok())
+    .and_then(|v| v.trim().parse().ok())
+    .map(Duration::from_secs);
+  Err(ResponseErr::RateLimited(after))
+}
@@ -0,0 +1,381 @@
+use reqwest::Url;
+use scraper::{ElementRef, Html, Selector};
+use serde::Deserialize;
+
+use crate::any::{
+  endpoints::Endpoints,
+  error::ResponseErr,
+  link::{self, LinkKind},
+};
+
+/// A web search engine used to find Musixmatch lyrics pages
+pub(crate) trait SearchBackend: Send + Sync {
+  /// Short name used to select the engine (like `google`)
+  fn name(&self) -> &'static str;
+
+  /// URL of the results page for a query
+  fn url(&self, query: &str) -> Result<Url, ResponseErr>;
+
+  /// Musixmatch lyrics URLs (and their description) found in a results page
+  fn parse(&self, page: &str) -> Result<Vec<(String, String)>, ResponseErr>;
+
+  /// The form giving the cookie consent `page` asks for, if it has one
+  fn consent_form(&self, _page: &str) -> Option<ConsentForm> {
+    None
+  }
+}
+
+/// A form to send, its `action` may be relative to the page it is in
+pub(crate) struct ConsentForm {
+  pub(crate) action: String,
+  pub(crate) fields: Vec<(String, String)>,
+}
+
+/// All engine names, in default fallback order
+pub(crate) const ENGINES: [&str; 4] = ["google", "musixmatch", "duckduckgo", "bing"];
+
+/// Search settings of the config file
+#[derive(Deserialize, Default, Clone)]
+#[serde(default)]
+pub(crate) struct SearchConfig {
+  pub(crate) google: GoogleSelectors,
+}
+
+/// CSS selectors of the Google results page, its classes change often so
+/// they can be fixed from the config without a new release
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub(crate) struct GoogleSelectors {
+  /// One search result, its link and title are looked for inside it
+  pub(crate) result: String,
+  /// Link of a result
+  pub(crate) link: String,
+  /// Title of a result
+  pub(crate) title: String,
+}
+
+impl Default for GoogleSelectors {
+  fn default() -> Self {
+    Self {
+      result: "div.g, div.MjjYud".into(),
+      link: r#"a[jsname="UWckNb"], a.VfSr4c, a[href]"#.into(),
+      title: "h3".into(),
+    }
+  }
+}
+
+pub(crate) fn by_name(name: &str, endpoints: &Endpoints, config: &SearchConfig) -> Result<Box<dyn SearchBackend>, String> {
+  match name {
+    "google" => Ok(Box::new(GoogleSearch::new(&endpoints.google, &config.google)?)),
+    "musixmatch" => Ok(Box::new(MusixmatchSearch {
+      base: endpoints.musixmatch.clone(),
+    })),
+    "duckduckgo" => Ok(Box::new(DuckDuckGoSearch {
+      base: endpoints.duckduckgo.clone(),
+    })),
+    "bing" => Ok(Box::new(BingSearch {
+      base: endpoints.bing.clone(),
+    })),
+    _ => Err(format!("Unknown search engine '{name}'")),
+  }
+}
+
+/// Every engine, `first` at the front and the rest in default order
+pub(crate) fn chain(first: &str, endpoints: &Endpoints, config: &SearchConfig) -> Result<Vec<Box<dyn SearchBackend>>, String> {
+  let first = by_name(first, endpoints, config)?;
+  let name = first.name();
+  let rest = ENGINES
+    .iter()
+    .filter(|n| **n != name)
+    .map(|n| by_name(n, endpoints, config))
+    .collect::<Result<Vec<_>, String>>()?;
+  Ok(std::iter::once(first).chain(rest).collect())
+}
+
+fn search_url(base: &str, path: &str, param: &str, query: &str) -> Result<Url, ResponseErr> {
+  let url = format!("{base}{path}");
+  Url::parse_with_params(&url, &[(param, query)]).map_err(|_| ResponseErr::InvalidUrl(url))
+}
+
+/// Keep only Musixmatch lyrics URLs, without duplicates
+fn keep_lyrics(found: impl Iterator<Item = (String, String)>) -> Result<Vec<(String, String)>, ResponseErr> {
+  let mut urls: Vec<(String, String)> = Vec::new();
+  for (url, desc) in found {
+    if link::musixmatch(&url, link::MUSIXMATCH_URL).is_some_and(|l| matches!(l.kind, LinkKind::MxmTrack { .. }))
+      && !urls.iter().any(|(u, _)| *u == url)
+    {
+      urls.push((url, desc.trim().to_string()));
+    }
+  }
+
+  if urls.is_empty() {
+    return Err(ResponseErr::NoEnoughData);
+  }
+  Ok(urls)
+}
+
+/// Google results page. Results are found by their (obfuscated) classes,
+/// or any link to a lyrics page, or the `/url?q=` redirects of the basic
+/// (no JavaScript) page, whatever works first
+pub(crate) struct GoogleSearch {
+  base: String,
+  result: Selector,
+  link: Selector,
+  title: Selector,
+}
+
+/// A way to find result links (and their title) in a Google page
+type Strategy = fn(&GoogleSearch, &Html) -> Vec<(String, String)>;
+
+impl GoogleSearch {
+  pub(crate) fn new(base: &str, selectors: &GoogleSelectors) -> Result<Self, String> {
+    let parse =
+      |name: &str, css: &str| Selector::parse(css).map_err(|e| format!("Invalid Google {name} selector '{css}': {e}"));
+    Ok(Self {
+      base: base.into(),
+      result: parse("result", &selectors.result)?,
+      link: parse("link", &selectors.link)?,
+      title: parse("title", &selectors.title)?,
+    })
+  }
+
+  /// Link and title of each result container, so they never get paired
+  /// with those of another result
+  fn by_class(&self, doc: &Html) -> Vec<(String, String)> {
+    doc
+      .select(&self.result)
+      .filter_map(|result| {
+        let href = result.select(&self.link).find_map(|a| a.value().attr("href"))?;
+        let title = result.select(&self.title).next().map_or_else(String::new, text);
+        Some((href.to_string(), title))
+      })
+      .collect()
+  }
+
+  /// Any absolute link to a lyrics page, titled by its heading (or its text)
+  fn by_href(&self, doc: &Html) -> Vec<(String, String)> {
+    let links = Selector::parse(r#"a[href^="http"][href*="musixmatch.com/lyrics"]"#).unwrap();
+    doc
+      .select(&links)
+      .filter_map(|a| Some((a.value().attr("href")?.to_string(), self.link_title(a))))
+      .collect()
+  }
+
+  /// Links through redirects, like `/url?q=<url>&sa=U`
+  fn by_redirect(&self, doc: &Html) -> Vec<(String, String)> {
+    let links = Selector::parse(r#"a[href^="/url?"]"#).unwrap();
+    doc
+      .select(&links)
+      .filter_map(|a| {
+        let href = Url::parse(&format!("{}{}", self.base, a.value().attr("href")?)).ok()?;
+        let url = href
+          .query_pairs()
+          .find(|(k, _)| k == "q" || k == "url")
+          .map(|(_, v)| v.into_owned())?;
+        Some((url, self.link_title(a)))
+      })
+      .collect()
+  }
+
+  fn link_title(&self, a: ElementRef) -> String {
+    a.select(&self.title).next().map_or_else(|| text(a), text)
+  }
+}
+
+impl SearchBackend for GoogleSearch {
+  fn name(&self) -> &'static str {
+    "google"
+  }
+
+  fn url(&self, query: &str) -> Result<Url, ResponseErr> {
+    search_url(
+      &self.base,
+      "/search",
+      "q",
+      &format!("site:musixmatch.com/lyrics lyrics {query}"),
+    )
+  }
+
+  fn parse(&self, page: &str) -> Result<Vec<(String, String)>, ResponseErr> {
+    if page.contains("/sorry/index") || page.contains(r#"id="captcha-form""#) {
+      return Err(ResponseErr::Blocked);
+    }
+
+    if page.contains(r#"/httpservice/retry/enablejs"#) {
+      return Err(ResponseErr::JsEnforcement);
+    }
+
+    if self.consent_form(page).is_some() {
+      return Err(ResponseErr::Consent);
+    }
+
+    if page.contains(r#"<script src="https://www.google.com/recaptcha/api.js" async defer></script>"#) {
+      return Err(ResponseErr::Captcha);
+    }
+
+    let doc = Html::parse_document(page);
+    let strategies: [Strategy; 3] = [Self::by_class, Self::by_href, Self::by_redirect];
+    strategies
+      .iter()
+      .find_map(|strategy| keep_lyrics(strategy(self, &doc).into_iter()).ok())
+      .ok_or_else(|| {
+        // Told apart from an empty results page only when nothing was found
+        match page.contains("Before you continue") || page.contains("consent.google.com") {
+          true => ResponseErr::Interstitial,
+          false => ResponseErr::NoEnoughData,
+        }
+      })
+  }
+
+  /// Consent pages have a form for each choice, "Reject all" is taken when
+  /// there (only the cookies needed to search are kept), "Accept all" if not
+  fn consent_form(&self, page: &str) -> Option<ConsentForm> {
+    let doc = Html::parse_document(page);
+    let forms = Selector::parse(r#"form[action*="consent.google."], form[action$="/save"]"#).unwrap();
+    let inputs = Selector::parse("input[name]").unwrap();
+
+    let forms: Vec<ConsentForm> = doc
+      .select(&forms)
+      .filter(|f| {
+        f.value()
+          .attr("method")
+          .is_some_and(|m| m.eq_ignore_ascii_case("post"))
+      })
+      .map(|f| ConsentForm {
+        action: f.value().attr("action").unwrap_or_default().into(),
+        fields: f
+          .select(&inputs)
+          .map(|i| {
+            (
+              i.value().attr("name").unwrap_or_default().into(),
+              i.value().attr("value").unwrap_or_default().into(),
+            )
+          })
+          .collect(),
+      })
+      .collect();
+    let reject = forms
+      .iter()
+      .position(|f| f.fields.iter().any(|(k, v)| k == "set_eom" && v == "true"));
+    forms.into_iter().nth(reject.unwrap_or_default())
+  }
+}
+
+/// Musixmatch own search page, links to lyrics pages are relative (and
+/// given back as public URLs)
+pub(crate) struct MusixmatchSearch {
+  base: String,
+}
+
+impl SearchBackend for MusixmatchSearch {
+  fn name(&self) -> &'static str {
+    "musixmatch"
+  }
+
+  fn url(&self, query: &str) -> Result<Url, ResponseErr> {
+    search_url(&self.base, "/search", "query", query)
+  }
+
+  fn parse(&self, page: &str) -> Result<Vec<(String, String)>, ResponseErr> {
+    let doc = Html::parse_document(page);
+    let links = Selector::parse(r#"a[href^="/lyrics/"]"#).unwrap();
+
+    keep_lyrics(doc.select(&links).filter_map(|a| {
+      let href = a.value().attr("href")?;
+      // Only track pages, like /lyrics/Artist/Title
+      if href.trim_matches('/').split('/').count() != 3 {
+        return None;
+      }
+      Some((
+        format!("{}{href}", link::MUSIXMATCH_URL),
+        a.text().collect::<Vec<_>>().join(" "),
+      ))
+    }))
+  }
+}
+
+/// DuckDuckGo HTML (no JavaScript) results page
+pub(crate) struct DuckDuckGoSearch {
+  base: String,
+}
+
+impl SearchBackend for DuckDuckGoSearch {
+  fn name(&self) -> &'static str {
+    "duckduckgo"
+  }
+
+  fn url(&self, query: &str) -> Result<Url, ResponseErr> {
+    search_url(
+      &self.base,
+      "/html/",
+      "q",
+      &format!("site:musixmatch.com/lyrics {query}"),
+    )
+  }
+
+  fn parse(&self, page: &str) -> Result<Vec<(String, String)>, ResponseErr> {
+    if page.contains("anomaly-modal") || page.contains(r#"id="challenge-form""#) {
+      return Err(ResponseErr::Captcha);
+    }
+
+    let doc = Html::parse_document(page);
+    let links = Selector::parse("a.result__a").unwrap();
+
+    keep_lyrics(doc.select(&links).filter_map(|a| {
+      let href = a.value().attr("href")?;
+      // Links go through a redirect, like //duckduckgo.com/l/?uddg=<url>
+      let url = match Url::parse(&format!("https:{href}")) {
+        Ok(u) if href.starts_with("//") => u
+          .query_pairs()
+          .find(|(k, _)| k == "uddg")
+          .map(|(_, v)| v.into_owned())?,
+        _ => href.to_string(),
+      };
+      Some((url, a.text().collect()))
+    }))
+  }
+}
+
+/// Bing results page
+pub(crate) struct BingSearch {
+  base: String,
+}
+
+impl SearchBackend for BingSearch {
+  fn name(&self) -> &'static str {
+    "bing"
+  }
+
+  fn url(&self, query: &str) -> Result<Url, ResponseErr> {
+    search_url(
+      &self.base,
+      "/search",
+      "q",
+      &format!("site:musixmatch.com/lyrics {query} lyrics"),
+    )
+  }
+
+  fn parse(&self, page: &str) -> Result<Vec<(String, String)>, ResponseErr> {
+    if page.contains(r#"id="b_captcha""#) || page.contains("/challenge/verify") {
+      return Err(ResponseErr::Captcha);
+    }
+
+    let doc = Html::parse_document(page);
+    let links = Selector::parse("li.b_algo h2 a").unwrap();
+
+    keep_lyrics(doc.select(&links).filter_map(|a| {
+      let href = a.value().attr("href")?;
+      Some((href.to_string(), a.text().collect()))
+    }))
+  }
+}
+
+/// Text of an element, whitespace collapsed
+fn text(el: ElementRef) -> String {
+  el.text()
+    .collect::<Vec<_>>()
+    .join(" ")
+    .split_whitespace()
+    .collect::<Vec<_>>()
+    .join(" ")
+}
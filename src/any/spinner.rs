@@ -1,5 +1,5 @@
 use rand::Rng;
-use std::io::Write;
+use std::io::{IsTerminal, Write};
 use std::sync::mpsc::{channel, Sender, TryRecvError};
 use std::sync::{Arc, Mutex};
 use std::thread;
@@ -21,6 +21,11 @@ impl Spinner {
 
   pub fn start(&mut self, hint: String) -> &mut Self {
     let (emtr, recv) = channel::<bool>();
+    self.channel = Some(emtr);
+    // Keep pipes and files clean, like JSON output
+    if !std::io::stdout().is_terminal() {
+      return self;
+    }
 
     self.handle = Some(thread::spawn(move || {
       let chars = vec!["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
@@ -28,10 +33,7 @@ impl Spinner {
       // Set cursor invisible
       print!("\x1b[?25l");
       loop {
-        let stop_me = match recv.try_recv() {
-          Ok(_) | Err(TryRecvError::Disconnected) => true,
-          _ => false,
-        };
+        let stop_me = matches!(recv.try_recv(), Ok(_) | Err(TryRecvError::Disconnected));
         if stop_me {
           break;
         }
@@ -44,15 +46,15 @@ impl Spinner {
         if i >= chars.len() {
           i = 0
         }
-        thread::sleep(std::time::Duration::from_millis(80 as u64));
+        thread::sleep(std::time::Duration::from_millis(80));
       }
       // Go to line start, delete from cursor to end, set cursor visible
       print!("\x1b[0K\x1b[?25h");
     }));
-    self.channel = Some(emtr);
     self
   }
 
+  #[allow(dead_code)]
   pub fn update(&mut self, hint: String) -> &mut Self {
     self.stop();
     self.start(hint)
@@ -63,14 +65,13 @@ impl Spinner {
     let mut lock = self.stop_flag.lock().unwrap();
     *lock = true;
 
-    self
-      .channel
-      .as_ref()
-      .unwrap()
-      .send(true)
-      .expect("Could not send stop signal");
-
     if let Some(handle) = self.handle.take() {
+      self
+        .channel
+        .as_ref()
+        .unwrap()
+        .send(true)
+        .expect("Could not send stop signal");
       handle.join().unwrap();
     }
   }
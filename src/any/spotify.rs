@@ -0,0 +1,157 @@
+use reqwest::Url;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::any::{
+  error::ResponseErr,
+  link,
+  mxm::extract_between,
+  proxy::{BlockingClients, ProxyPool},
+  uagent,
+};
+
+/// Typed view of the embed page `__NEXT_DATA__`, only the parts in use
+#[derive(Deserialize)]
+struct EmbedPage {
+  props: EmbedProps,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EmbedProps {
+  page_props: EmbedPageProps,
+}
+
+#[derive(Deserialize)]
+struct EmbedPageProps {
+  state: EmbedState,
+}
+
+#[derive(Deserialize)]
+struct EmbedState {
+  data: EmbedData,
+}
+
+#[derive(Deserialize)]
+struct EmbedData {
+  entity: EmbedEntity,
+}
+
+#[derive(Deserialize)]
+struct EmbedEntity {
+  name: String,
+  #[serde(default)]
+  artists: Vec<EmbedArtist>,
+}
+
+#[derive(Deserialize)]
+struct EmbedArtist {
+  name: String,
+}
+
+/// The oEmbed response, its title is the song name only
+#[derive(Deserialize)]
+struct OEmbed {
+  title: String,
+}
+
+/// What a Spotify track is, enough to search its lyrics
+pub(crate) struct SpotifyTrack {
+  /// Empty when only the oEmbed data could be read
+  pub(crate) artist: String,
+  pub(crate) title: String,
+}
+
+impl SpotifyTrack {
+  /// Search query for the lyrics providers
+  pub(crate) fn query(&self) -> String {
+    format!("{} {}", self.artist, self.title).trim().to_string()
+  }
+}
+
+/// Client for the public Spotify track pages, no account (nor token) needed
+pub(crate) struct SpotifyAPI {
+  base_url: String,
+  timeout: u32,
+  clients: BlockingClients,
+}
+
+impl SpotifyAPI {
+  pub(crate) fn new(base_url: &str, timeout: u32) -> Self {
+    Self {
+      base_url: base_url.trim_end_matches('/').to_string(),
+      timeout,
+      clients: BlockingClients::default(),
+    }
+  }
+
+  /// Send every request through the proxies of `pool`
+  pub(crate) fn proxies(mut self, pool: Arc<ProxyPool>) -> Self {
+    self.clients = BlockingClients::new(Some(pool));
+    self
+  }
+
+  /// Artist and title of the track at a Spotify URL or URI, from its embed
+  /// page, or its oEmbed data (title only) when the embed page changed
+  pub(crate) fn resolve(&self, url: &str) -> Result<SpotifyTrack, ResponseErr> {
+    let link = link::spotify(url).ok_or(ResponseErr::InvalidUrl(url.into()))?;
+    let id = link.url.rsplit('/').next().unwrap_or_default();
+    self
+      .embed(id)
+      .or_else(|e| self.oembed(&link.url).map_err(|_| e))
+  }
+
+  fn embed(&self, id: &str) -> Result<SpotifyTrack, ResponseErr> {
+    let url = format!("{}/embed/track/{id}", self.base_url);
+    let url = Url::parse(&url).map_err(|_| ResponseErr::InvalidUrl(url))?;
+    let html = self.get_text(url)?;
+    let json = extract_between(
+      &html,
+      r#"<script id="__NEXT_DATA__" type="application/json">"#,
+      "</script>",
+    )
+    .ok_or(ResponseErr::parse("Spotify embed page data"))?;
+
+    let mut de = serde_json::Deserializer::from_str(json);
+    let page: EmbedPage =
+      serde_path_to_error::deserialize(&mut de).map_err(|e| ResponseErr::parse_with("Spotify embed page data", e))?;
+    let entity = page.props.page_props.state.data.entity;
+    Ok(SpotifyTrack {
+      artist: entity
+        .artists
+        .into_iter()
+        .map(|a| a.name)
+        .collect::<Vec<_>>()
+        .join(" "),
+      title: entity.name,
+    })
+  }
+
+  fn oembed(&self, track_url: &str) -> Result<SpotifyTrack, ResponseErr> {
+    let endpoint = format!("{}/oembed", self.base_url);
+    let url = Url::parse_with_params(&endpoint, &[("url", track_url)]).map_err(|_| ResponseErr::InvalidUrl(endpoint))?;
+    let body = self.get_text(url)?;
+    let mut de = serde_json::Deserializer::from_str(&body);
+    let res: OEmbed =
+      serde_path_to_error::deserialize(&mut de).map_err(|e| ResponseErr::parse_with("Spotify oEmbed response", e))?;
+    Ok(SpotifyTrack {
+      artist: String::new(),
+      title: res.title,
+    })
+  }
+
+  fn get_text(&self, url: Url) -> Result<String, ResponseErr> {
+    self.clients.send(|client| {
+      Ok(
+        client
+          .get(url.clone())
+          .header(reqwest::header::USER_AGENT, uagent::random())
+          .timeout(Duration::from_millis(self.timeout as u64))
+          .send()?
+          .error_for_status()?
+          .text()?,
+      )
+    })
+  }
+}
@@ -0,0 +1,361 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::time::Duration;
+
+use crate::any::{genius::GeniusAPI, lrclib::LrclibAPI, mxm::MxmAPI, rank};
+
+/// A search result, a song page URL and a short description of it
+#[derive(Serialize, Deserialize)]
+pub(crate) struct TrackItem {
+  pub(crate) url: String,
+  pub(crate) desc: String,
+  /// Translation languages found for this URL
+  pub(crate) translations: Vec<String>,
+  /// How well it matches the query, from 0 to 1, once ranked
+  #[serde(skip)]
+  pub(crate) score: Option<f64>,
+}
+
+impl TrackItem {
+  pub(crate) fn new(url: String, desc: String) -> Self {
+    Self {
+      url,
+      desc,
+      translations: Vec::new(),
+      score: None,
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct LyricParagraph {
+  #[serde(deserialize_with = "line_texts")]
+  pub(crate) lines: Vec<String>,
+  pub(crate) title: String, // Discard type, its always lyrics
+}
+
+/// Lines come as `{"text": "..."}` objects, only the text is kept. Plain
+/// strings are taken too, like serialized ones are
+fn line_texts<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+  #[derive(Deserialize)]
+  #[serde(untagged)]
+  enum Line {
+    Object { text: String },
+    Text(String),
+  }
+
+  let lines = Vec::<Line>::deserialize(deserializer)?;
+  Ok(
+    lines
+      .into_iter()
+      .map(|l| match l {
+        Line::Object { text } | Line::Text(text) => text,
+      })
+      .collect(),
+  )
+}
+
+/// Times are kept as milliseconds, plain numbers scripts can read
+fn as_millis<S: Serializer>(time: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+  serializer.serialize_u64(time.as_millis() as u64)
+}
+
+fn from_millis<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+  u64::deserialize(deserializer).map(Duration::from_millis)
+}
+
+/// Words as `[offset, text]` pairs, the offset in milliseconds
+fn words_as_millis<S: Serializer>(words: &[(Duration, String)], serializer: S) -> Result<S::Ok, S::Error> {
+  serializer.collect_seq(
+    words
+      .iter()
+      .map(|(offset, word)| (offset.as_millis() as u64, word)),
+  )
+}
+
+fn words_from_millis<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<(Duration, String)>, D::Error> {
+  let words = Vec::<(u64, String)>::deserialize(deserializer)?;
+  Ok(
+    words
+      .into_iter()
+      .map(|(offset, word)| (Duration::from_millis(offset), word))
+      .collect(),
+  )
+}
+
+/// A lyrics line and the moment it starts being sung
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SyncedLine {
+  /// Milliseconds from the start of the song
+  #[serde(serialize_with = "as_millis", deserialize_with = "from_millis")]
+  pub(crate) time: Duration,
+  pub(crate) text: String,
+  /// Index of the `LyricParagraph` (in `TrackInfo::lyrics_struct`) the line belongs to
+  pub(crate) section: Option<usize>,
+}
+
+impl SyncedLine {
+  /// Link every line to its paragraph, walking both in order so repeated
+  /// lines (like choruses) land in the right section
+  pub(crate) fn link_sections(lines: &mut [SyncedLine], paragraphs: &[LyricParagraph]) {
+    let flat: Vec<(usize, &str)> = paragraphs
+      .iter()
+      .enumerate()
+      .flat_map(|(i, p)| p.lines.iter().map(move |l| (i, l.trim())))
+      .collect();
+
+    let mut cursor = 0;
+    for line in lines.iter_mut() {
+      let text = line.text.trim();
+      if text.is_empty() {
+        continue;
+      }
+      if let Some(pos) = flat[cursor..].iter().position(|(_, l)| *l == text) {
+        line.section = Some(flat[cursor + pos].0);
+        cursor += pos + 1;
+      }
+    }
+  }
+}
+
+/// A lyrics line with per-word timing (Musixmatch richsync)
+#[derive(Serialize, Deserialize)]
+pub(crate) struct RichSyncLine {
+  /// Milliseconds from the start of the song
+  #[serde(serialize_with = "as_millis", deserialize_with = "from_millis")]
+  pub(crate) start: Duration,
+  #[serde(serialize_with = "as_millis", deserialize_with = "from_millis")]
+  pub(crate) end: Duration,
+  /// Words (and the spaces between them) with their offset from `start`
+  #[serde(
+    serialize_with = "words_as_millis",
+    deserialize_with = "words_from_millis"
+  )]
+  pub(crate) words: Vec<(Duration, String)>,
+}
+/// Trying to not have this in main.rs, here is it
+#[derive(Serialize, Deserialize)]
+pub(crate) struct TrackInfo {
+  /// Song title
+  pub(crate) name: String,
+  /// Song artist/group
+  pub(crate) artist: String,
+  /// Album containing the song
+  pub(crate) album: Option<String>,
+  /// Album page URL, when the provider has album pages
+  #[serde(default)]
+  pub(crate) album_url: Option<String>,
+  /// Whether lyrics are available, or why they are not
+  pub(crate) lyrics_status: LyricsStatus,
+  /// Whether song has lyrics structure or not
+  pub(crate) has_lyrics_struct: bool,
+  /// Lyrics (full) language name
+  pub(crate) lyrics_lang: Option<String>,
+  /// Lyrics ISO 639-1 language code, like `en`
+  #[serde(default)]
+  pub(crate) lyrics_lang_code: Option<String>,
+  /// The lyrics as string (escaped), empty when there are none
+  pub(crate) lyrics: String,
+  /// A structured lyrics representation
+  pub(crate) lyrics_struct: Vec<LyricParagraph>,
+  /// Time-synced lyrics lines, if available
+  pub(crate) synced: Option<Vec<SyncedLine>>,
+  /// Word-synced lyrics lines, if available
+  pub(crate) rich_synced: Option<Vec<RichSyncLine>>,
+  /// Lyric composer(s)
+  pub(crate) lyrics_copyright: Option<String>,
+  /// Song primary genre
+  pub(crate) genre: Option<String>,
+  /// Cover image URL (expect a 350x350 px jpg file url)
+  pub(crate) cover: Option<String>,
+  /// Song release date in format YYYY-MM-DD
+  pub(crate) released: Option<String>,
+  /// Song length in seconds
+  #[serde(default)]
+  pub(crate) duration: Option<u32>,
+  /// International Standard Recording Code
+  #[serde(default)]
+  pub(crate) isrc: Option<String>,
+  /// Whether the lyrics have explicit content
+  #[serde(default)]
+  pub(crate) explicit: bool,
+  /// Whether the song has no vocals
+  #[serde(default)]
+  pub(crate) instrumental: bool,
+  /// Songwriters
+  #[serde(default)]
+  pub(crate) writers: Vec<String>,
+  /// Music publishers
+  #[serde(default)]
+  pub(crate) publishers: Vec<String>,
+  /// Song ID on the provider it was taken from
+  #[serde(default)]
+  pub(crate) track_id: Option<u64>,
+  /// Spotify URL for this song
+  pub(crate) spotify: Option<String>,
+  /// Song page URL, on the provider it was taken from
+  pub(crate) url: String,
+  /// Name of the provider the data was taken from
+  #[serde(deserialize_with = "provider_name")]
+  pub(crate) source: ProviderName,
+}
+
+/// An album and its songs, in tracklist order
+#[derive(Serialize, Deserialize)]
+pub(crate) struct AlbumInfo {
+  pub(crate) name: String,
+  pub(crate) artist: String,
+  /// Album release date in format YYYY-MM-DD
+  pub(crate) released: Option<String>,
+  /// Album page URL
+  pub(crate) url: String,
+  /// Song pages, with the song title as description
+  pub(crate) tracks: Vec<TrackItem>,
+}
+
+/// An album of an artist discography, its songs are in its page
+#[derive(Serialize, Deserialize)]
+pub(crate) struct AlbumItem {
+  pub(crate) name: String,
+  /// Album release date in format YYYY-MM-DD
+  pub(crate) released: Option<String>,
+  /// Album page URL
+  pub(crate) url: String,
+}
+
+/// An artist and their albums, in the order of the artist page
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ArtistInfo {
+  pub(crate) name: String,
+  /// Artist page URL
+  pub(crate) url: String,
+  pub(crate) albums: Vec<AlbumItem>,
+}
+
+impl TrackInfo {
+  /// Whether song has lyrics and they can be shown
+  pub(crate) fn has_lyrics(&self) -> bool {
+    self.lyrics_status == LyricsStatus::Available
+  }
+}
+
+/// Whether the lyrics of a song can be shown, or why not
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum LyricsStatus {
+  Available,
+  /// The song has no vocals
+  Instrumental,
+  /// Lyrics exist, but can't be shown in this region
+  Restricted,
+  /// Lyrics were added, but are waiting for review (unverified)
+  Pending,
+  /// Nobody added lyrics for this song
+  Missing,
+}
+
+impl LyricsStatus {
+  pub(crate) fn message(self) -> &'static str {
+    match self {
+      Self::Available => "Lyrics are available",
+      Self::Instrumental => "This song is instrumental, it has no lyrics",
+      Self::Restricted => "Lyrics of this song are restricted in your region",
+      Self::Pending => "Lyrics of this song are pending review, try again later",
+      Self::Missing => "Lyrics are not available :(",
+    }
+  }
+}
+
+/// Behind an alias so serde does not tie the deserializer lifetime to `'static`
+type ProviderName = &'static str;
+
+/// Provider names are constants, map the stored one back to it
+fn provider_name<'de, D: Deserializer<'de>>(deserializer: D) -> Result<&'static str, D::Error> {
+  let name = String::deserialize(deserializer)?;
+  [MxmAPI::NAME, GeniusAPI::NAME, LrclibAPI::NAME]
+    .into_iter()
+    .find(|n| *n == name)
+    .ok_or_else(|| serde::de::Error::custom(format!("unknown provider '{name}'")))
+}
+
+/// Song metadata to search for, every field is optional
+#[derive(Default)]
+pub(crate) struct TrackQuery {
+  pub(crate) title: Option<String>,
+  pub(crate) artist: Option<String>,
+  pub(crate) album: Option<String>,
+  /// Song length in seconds
+  pub(crate) duration: Option<u32>,
+  /// Searched as text only when there is nothing else, always checked
+  pub(crate) isrc: Option<String>,
+}
+
+impl TrackQuery {
+  /// Seconds a song length may be off, like LRCLIB allows
+  const DURATION_TOLERANCE: u32 = 2;
+
+  pub(crate) fn is_empty(&self) -> bool {
+    self.title.is_none() && self.artist.is_none() && self.album.is_none() && self.duration.is_none() && self.isrc.is_none()
+  }
+
+  /// Whether there is something to search with, not only to check
+  pub(crate) fn is_searchable(&self) -> bool {
+    self.title.is_some() || self.artist.is_some() || self.album.is_some() || self.isrc.is_some()
+  }
+
+  /// Free text form for providers that only search by text, the album
+  /// (or else the ISRC) only when there is nothing else
+  pub(crate) fn text(&self) -> String {
+    match (&self.artist, &self.title) {
+      (None, None) => self
+        .album
+        .clone()
+        .or_else(|| self.isrc.clone())
+        .unwrap_or_default(),
+      (artist, title) => [artist.as_deref(), title.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" "),
+    }
+  }
+
+  /// Why `track` is not the song asked for, `None` when every given field
+  /// matches. Title and album must have the same words, the artist may be
+  /// followed by others (like `feat. ...`), fields the provider does not
+  /// give do not match
+  pub(crate) fn mismatch(&self, track: &TrackInfo) -> Option<String> {
+    let words = rank::words;
+    if let Some(title) = &self.title {
+      if words(title) != words(&track.name) {
+        return Some(format!("title is '{}'", track.name));
+      }
+    }
+    if let Some(artist) = &self.artist {
+      if !words(&track.artist).starts_with(&words(artist)) {
+        return Some(format!("artist is '{}'", track.artist));
+      }
+    }
+    if let Some(album) = &self.album {
+      match &track.album {
+        Some(a) if words(album) == words(a) => (),
+        Some(a) => return Some(format!("album is '{a}'")),
+        None => return Some("album is unknown".into()),
+      }
+    }
+    if let Some(isrc) = &self.isrc {
+      match &track.isrc {
+        Some(i) if i.eq_ignore_ascii_case(isrc) => (),
+        Some(i) => return Some(format!("ISRC is {i}")),
+        None => return Some("ISRC is unknown".into()),
+      }
+    }
+    if let Some(duration) = self.duration {
+      match track.duration {
+        Some(d) if d.abs_diff(duration) <= Self::DURATION_TOLERANCE => (),
+        Some(d) => return Some(format!("duration is {d}s")),
+        None => return Some("duration is unknown".into()),
+      }
+    }
+    None
+  }
+}
@@ -77,9 +77,5 @@ pub fn random() -> String {
   "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/139.0.0.0 Safari/537.36",
   "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/99.0.4844.51 Safari/537.36"
 ];
-  return ua
-    .choose(&mut rand::thread_rng())
-    .unwrap()
-    .to_owned()
-    .to_owned();
+  ua.choose(&mut rand::thread_rng()).unwrap().to_string()
 }
@@ -15,6 +15,7 @@ pub(crate) struct IrcInfo<'a> {
 }
 
 #[allow(dead_code)]
+#[allow(clippy::upper_case_acronyms)] // Named after the IRC commands
 #[derive(Debug)]
 pub(crate) enum TwitchIrcMsg<'a> {
   /// Sent when the bot or moderator removes all messages from the chat room or removes all messages for the specified user
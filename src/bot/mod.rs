@@ -6,6 +6,7 @@ mod twitch;
 use crate::any::macros;
 use std::{io::BufRead, rc::Rc};
 
+#[allow(dead_code)] // Not wired yet, see lyrbot in main()
 pub(crate) fn bot(channel: &str, username: &str, token: &str) {
   let mut twitch = twitch::TwitchHandler::new(channel, username);
   let twitch = Rc::from(twitch.login(token));
@@ -14,9 +15,10 @@ pub(crate) fn bot(channel: &str, username: &str, token: &str) {
   let mut lines = reader.lines();
 
   while let Some(Ok(ln)) = lines.next() {
-    let msg = irc::parse_message(&ln.as_str());
+    let msg = irc::parse_message(ln.as_str());
     macros::log_inf!("Message: {:#?}", msg);
 
+    #[allow(clippy::single_match)]
     match msg {
       irc::TwitchIrcMsg::JOIN => {
         macros::log_ok!("Successfully authenticated and joined {}", channel);
@@ -2,7 +2,7 @@ use crate::any::macros;
 use std::io::{BufReader, Write};
 use std::net::TcpStream;
 
-const TWITCH_IRC: &'static str = "irc.chat.twitch.tv:6667";
+const TWITCH_IRC: &str = "irc.chat.twitch.tv:6667";
 // const REQUEST_DELAY: u64 = 1000;
 
 pub(crate) struct TwitchHandler<'a> {
@@ -38,6 +38,7 @@ impl<'a> TwitchHandler<'a> {
     self
   }
 
+  #[allow(dead_code)]
   pub(crate) fn send(&mut self, data: String) -> &Self {
     let data = format!("PRIVMSG #{} :{}", self.channel, data);
     socket_send_raw(&mut self.socket, data);
@@ -49,7 +50,7 @@ impl<'a> TwitchHandler<'a> {
   }
 }
 
-pub(crate) fn socket_send_raw(socket: &mut TcpStream, data: String) -> () {
+pub(crate) fn socket_send_raw(socket: &mut TcpStream, data: String) {
   let data = format!("{data}\r\n");
   let write = socket.write(data.as_bytes());
   if let Err(e) = write {
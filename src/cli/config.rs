@@ -0,0 +1,84 @@
+use serde::Deserialize;
+use std::path::PathBuf;
+
+use crate::any::{endpoints::Endpoints, retry::RetryPolicy, search::SearchConfig};
+
+/// CLI settings file, every key is optional
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub(crate) struct Config {
+  pub(crate) endpoints: Endpoints,
+  /// Where cookies are kept between runs
+  pub(crate) cookie_jar: Option<PathBuf>,
+  pub(crate) retry: RetryPolicy,
+  pub(crate) cache: CacheConfig,
+  pub(crate) search: SearchConfig,
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub(crate) struct CacheConfig {
+  /// Where cached data is kept
+  pub(crate) dir: Option<PathBuf>,
+  /// Seconds an entry is used for
+  pub(crate) ttl: u64,
+}
+
+impl Default for CacheConfig {
+  fn default() -> Self {
+    Self {
+      dir: None,
+      ttl: 7 * 24 * 60 * 60,
+    }
+  }
+}
+
+impl CacheConfig {
+  /// `$LYRICS_CACHE_DIR`, the config one, or `lyrics` in the user cache directory
+  pub(crate) fn dir(&self) -> Option<PathBuf> {
+    match std::env::var_os("LYRICS_CACHE_DIR") {
+      Some(p) => Some(PathBuf::from(p)),
+      None => self
+        .dir
+        .clone()
+        .or_else(|| Some(dirs::cache_dir()?.join("lyrics"))),
+    }
+  }
+}
+
+impl Config {
+  /// `$LYRICS_COOKIE_JAR`, the config one, or `cookies.json` in the `lyrics`
+  /// user data directory
+  pub(crate) fn cookie_jar(&self) -> Option<PathBuf> {
+    match std::env::var_os("LYRICS_COOKIE_JAR") {
+      Some(p) => Some(PathBuf::from(p)),
+      None => self
+        .cookie_jar
+        .clone()
+        .or_else(|| Some(dirs::data_local_dir()?.join("lyrics").join("cookies.json"))),
+    }
+  }
+}
+
+/// `$LYRICS_CONFIG`, or `config.json` in the `lyrics` user config directory
+fn path() -> Option<PathBuf> {
+  match std::env::var_os("LYRICS_CONFIG") {
+    Some(p) => Some(PathBuf::from(p)),
+    None => Some(dirs::config_dir()?.join("lyrics").join("config.json")),
+  }
+}
+
+/// Read the config file (defaults if there is none), environment variables
+/// take precedence over it
+pub(crate) fn load() -> Result<Config, String> {
+  let mut cfg = match path() {
+    Some(p) if p.is_file() => {
+      let r = std::fs::read_to_string(&p).map_err(|e| format!("Could not read {}: {e}", p.display()))?;
+      serde_json::from_str(&r).map_err(|e| format!("Invalid config file {}: {e}", p.display()))?
+    }
+    _ => Config::default(),
+  };
+
+  cfg.endpoints = cfg.endpoints.with_env();
+  Ok(cfg)
+}
@@ -1,53 +1,223 @@
+mod config;
+
 use crate::any::{
+  cache::{Cache, CacheMode},
+  endpoints::Endpoints,
+  error::ResponseErr,
+  genius::GeniusAPI,
+  link::{self, Link, LinkKind},
+  lrc,
+  lrclib::LrclibAPI,
   macros,
-  mxm::{MxmAPI, TrackItem},
+  mxm::{MxmAPI, Translation},
+  provider::{LyricsProvider, Providers},
+  proxy::{self, ProxyPool},
+  search,
+  spotify::SpotifyAPI,
+  track::{AlbumInfo, LyricsStatus, TrackInfo, TrackItem, TrackQuery},
   uagent,
 };
-use clap::Parser;
+use clap::{ArgGroup, Parser, Subcommand};
 use reqwest::header::{HeaderMap, HeaderValue};
+use std::error::Error;
 use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 #[derive(Parser)]
+#[clap(group(ArgGroup::new("fields").multiple(true)))]
+#[clap(args_conflicts_with_subcommands = true)]
 struct Args {
+  #[clap(subcommand)]
+  command: Option<Command>,
   ///The search query or URL for the music
   query: Vec<String>,
   ///Timeout timeout in milliseconds
-  #[clap(short = 't', long = "timeout", default_value = "5000")]
+  #[clap(
+    short = 't',
+    long = "timeout",
+    default_value = "5000",
+    global = true
+  )]
   timeout: u32,
-  ///Number of trying attempts to get data
-  #[clap(short = 'T', long = "tries", default_value = "5")]
-  tries: u32,
-  ///URL index, use -a to view all URLs
-  #[clap(short = 'i', long = "url-index", default_value = "0")]
+  ///Number of trying attempts to get data [default: 5]
+  #[clap(short = 'T', long = "tries", default_value = None, global = true)]
+  tries: Option<u32>,
+  ///Milliseconds to wait before the second try, doubled on each failure [default: 250]
+  #[clap(long = "backoff", default_value = None, global = true)]
+  backoff: Option<u64>,
+  ///Longest wait between two tries, in milliseconds [default: 8000]
+  #[clap(long = "max-backoff", default_value = None, global = true)]
+  max_backoff: Option<u64>,
+  ///Longest wait a rate limit (Retry-After) may ask for, in milliseconds, longer ones fail [default: 60000]
+  #[clap(long = "max-retry-after", default_value = None, global = true)]
+  max_retry_after: Option<u64>,
+  ///Connection timeout in milliseconds [default: 3000]
+  #[clap(long = "connect-timeout", default_value = None, global = true)]
+  connect_timeout: Option<u64>,
+  ///Timeout between two reads of a response, in milliseconds [default: 5000]
+  #[clap(long = "read-timeout", default_value = None, global = true)]
+  read_timeout: Option<u64>,
+  ///Time limit for each step (search, fetch), tries and waits included, in milliseconds
+  #[clap(long = "deadline", default_value = None, global = true)]
+  deadline: Option<u64>,
+  ///Song title, the query (if any) is taken as the title when not given
+  #[clap(long = "title", group = "fields")]
+  title: Option<String>,
+  ///Song artist
+  #[clap(long = "artist", group = "fields")]
+  artist: Option<String>,
+  ///Song album
+  #[clap(long = "album", group = "fields")]
+  album: Option<String>,
+  ///Song ISRC, to check the song found, or to search with when alone (like USWB10300474)
+  #[clap(long = "isrc", group = "fields")]
+  isrc: Option<String>,
+  ///Song length in seconds, to check the song found (2 seconds off allowed)
+  #[clap(long = "duration", group = "fields")]
+  duration: Option<u32>,
+  ///URL index, results are sorted best match first, use -a to view all URLs
+  #[clap(
+    short = 'i',
+    long = "url-index",
+    default_value = "0",
+    global = true
+  )]
   url_index: usize,
-  // ---------------------------------------
-  // Changes to MxmAPI needed
-  //
-  // ///Proxy address to use
-  // #[clap(short = 'p', long = "proxy", default_value = None)]
-  // proxy: Option<String>,
-  // ///Proxy list file to read from
-  // #[clap(short = 'P', long = "proxylist", default_value = None)]
-  // proxylist: Option<String>,
-  // ---------------------------------------
+  ///Proxy address to use (http, https or socks5, like socks5://127.0.0.1:9050)
+  #[clap(short = 'p', long = "proxy", default_value = None, global = true)]
+  proxy: Option<String>,
+  ///Proxy list file to read from, one per line, the next is used when one fails
+  #[clap(short = 'P', long = "proxylist", default_value = None, global = true)]
+  proxylist: Option<String>,
   ///Cookie string for Google
-  #[clap(short = 'C', long = "ggl-cookies", default_value = None)]
+  #[clap(short = 'C', long = "ggl-cookies", default_value = None, global = true)]
   ggl_cookie: Option<String>,
   ///User agent string
-  #[clap(short = 'U', long = "user-agent", default_value = None)]
+  #[clap(short = 'U', long = "user-agent", default_value = None, global = true)]
   user_agent: Option<String>,
+  ///Lyrics providers to try, in order, when one has no lyrics the next is used
+  #[clap(
+    long = "providers",
+    value_delimiter = ',',
+    default_value = "musixmatch,genius,lrclib"
+  )]
+  providers: Vec<String>,
+  ///Base URL of the Genius site (overrides config and LYRICS_GENIUS_URL)
+  #[clap(long = "genius-url", default_value = None)]
+  genius_url: Option<String>,
+  ///Base URL of the LRCLIB (compatible) server (overrides config and LYRICS_LRCLIB_URL)
+  #[clap(long = "lrclib-url", default_value = None)]
+  lrclib_url: Option<String>,
+  ///Search engine to find Musixmatch pages with, the others are used as fallback
+  #[clap(long = "search-engine", default_value = "google", value_parser = search::ENGINES, global = true)]
+  search_engine: String,
   ///Show URL found and ask user to select one
-  #[clap(short = 'a', long = "tip-url", default_value = "false")]
+  #[clap(
+    short = 'a',
+    long = "tip-url",
+    default_value = "false",
+    conflicts_with = "fields"
+  )]
   typ_url: bool,
+  ///Print all the song data as JSON
+  #[clap(
+    short = 'j',
+    long = "json",
+    default_value = "false",
+    conflicts_with_all = ["only_lyrics", "synced", "word_synced"]
+  )]
+  json: bool,
   ///Only print lyrics (With -r is a bit different)
   #[clap(short = 'l', long = "lyrics", default_value = "false")]
   only_lyrics: bool,
   ///Show album cover art (prefer using a terminal with image support)
   #[clap(short = 'c', long = "cover-art", default_value = "false")]
   show_cover: bool,
+  ///Only print time-synced lyrics, in LRC format
+  #[clap(short = 'S', long = "synced", default_value = "false")]
+  synced: bool,
+  ///Only print word-synced lyrics, in Enhanced LRC format
+  #[clap(short = 'W', long = "word-synced", default_value = "false")]
+  word_synced: bool,
+  ///Also print the lyrics translated to this language (code, like es)
+  #[clap(short = 'x', long = "translate", default_value = None)]
+  translate: Option<String>,
+  ///Print translations side by side instead of below each line
+  #[clap(long = "side-by-side", default_value = "false")]
+  side_by_side: bool,
   ///Print <Track name> - <Artist> before printing lyrics
   #[clap(short = 'r', long = "repeat", default_value = "false")]
   repeat: bool,
+  ///Do not read nor write the cache
+  #[clap(long = "no-cache", default_value = "false", conflicts_with_all = ["refresh", "offline"], global = true)]
+  no_cache: bool,
+  ///Ignore cached data, but store the new one
+  #[clap(
+    long = "refresh",
+    default_value = "false",
+    conflicts_with = "offline",
+    global = true
+  )]
+  refresh: bool,
+  ///Only use cached data (expired included), send no requests
+  #[clap(long = "offline", default_value = "false", global = true)]
+  offline: bool,
+  ///Seconds cached data is used for [default: 604800]
+  #[clap(long = "cache-ttl", default_value = None, global = true)]
+  cache_ttl: Option<u64>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+  ///Get the lyrics of every song of a Musixmatch album
+  Album {
+    ///Album URL, or a song URL or query to take the album from
+    #[clap(required = true)]
+    query: Vec<String>,
+    ///Save one file per song to this directory instead of printing them
+    #[clap(short = 'o', long = "output-dir", default_value = None)]
+    output_dir: Option<PathBuf>,
+  },
+  ///List the albums and songs of a Musixmatch artist
+  Artist {
+    ///Artist URL, or a song or album URL or query to take the artist from
+    #[clap(required = true)]
+    query: Vec<String>,
+    ///Only list the albums, without getting their songs
+    #[clap(long = "albums-only", default_value = "false")]
+    albums_only: bool,
+    ///Get the lyrics of every song into the cache
+    #[clap(
+      long = "prefetch",
+      default_value = "false",
+      conflicts_with = "albums_only"
+    )]
+    prefetch: bool,
+    ///Pages of albums to read at most
+    #[clap(long = "max-pages", default_value = "20")]
+    max_pages: u32,
+    ///Milliseconds between two requests, to be polite with the site
+    #[clap(long = "delay", default_value = "1000")]
+    delay: u64,
+  },
+  ///Manage cached songs, searches and covers
+  Cache {
+    #[clap(subcommand)]
+    action: CacheAction,
+  },
+}
+
+#[derive(Subcommand)]
+enum CacheAction {
+  ///List cached entries, newest first
+  List,
+  ///Remove expired entries
+  Prune,
+  ///Remove every entry
+  Clear,
 }
 
 macro_rules! header_add {
@@ -62,29 +232,74 @@ macro_rules! header_add {
 /// The CLI functionality
 pub fn cli() {
   let args = Args::parse();
+  let cfg = config::load().unwrap_or_else(|e| {
+    macros::exit_err!("{e}");
+  });
+
+  let cache_mode = if args.offline {
+    CacheMode::Offline
+  } else if args.refresh {
+    CacheMode::Refresh
+  } else {
+    CacheMode::Normal
+  };
+  let cache = cfg.cache.dir().map(|dir| {
+    let ttl = args.cache_ttl.unwrap_or(cfg.cache.ttl);
+    Cache::new(dir, Duration::from_secs(ttl), cache_mode)
+  });
+
+  if let Some(Command::Cache { action }) = &args.command {
+    let cache = cache.unwrap_or_else(|| {
+      macros::exit_err!("There is no cache directory in this system");
+    });
+    cache_command(action, &cache);
+    std::process::exit(0);
+  }
 
   // Parse some command line arguments items as groups
-  if args.query.len() < 1 {
+  let mut fields = TrackQuery {
+    title: args.title.clone(),
+    artist: args.artist.clone(),
+    album: args.album.clone(),
+    duration: args.duration,
+    isrc: args.isrc.clone(),
+  };
+  if args.query.is_empty() && fields.is_empty() && args.command.is_none() {
     macros::exit_err!("You must specify a url or query to get a url");
   }
+  if !fields.is_empty() && args.command.is_none() {
+    // Checked here as a global argument can't conflict with the group
+    if args.url_index != 0 {
+      macros::exit_err!("--url-index picks a search result, it can't be used with the song fields");
+    }
+    match (fields.title.is_some(), args.query.is_empty()) {
+      (true, false) => {
+        macros::exit_err!("Give the title either as the query or with --title, not both");
+      }
+      (false, false) => fields.title = Some(args.query.join(" ")),
+      _ => (),
+    }
+    if !fields.is_searchable() {
+      macros::exit_err!("--duration only checks the song found, give a title, artist, album or ISRC to search with");
+    }
+  }
+
+  let mut retry = cfg.retry.clone();
+  retry.tries = args.tries.unwrap_or(retry.tries);
+  retry.backoff = args.backoff.unwrap_or(retry.backoff);
+  retry.max_backoff = args.max_backoff.unwrap_or(retry.max_backoff);
+  retry.max_retry_after = args.max_retry_after.unwrap_or(retry.max_retry_after);
+  retry.connect_timeout = args.connect_timeout.unwrap_or(retry.connect_timeout);
+  retry.read_timeout = args.read_timeout.unwrap_or(retry.read_timeout);
+  retry.deadline = args.deadline.or(retry.deadline);
 
-  if args.tries == 0 {
+  if retry.tries == 0 {
     macros::exit_err!("--tries/-T cannot accept 0");
   }
 
   let mut headers = HeaderMap::new();
-  if args.user_agent.is_none() {
-    headers.insert(reqwest::header::USER_AGENT, uagent::random().parse().unwrap());
-  } else {
-    headers.insert(
-      reqwest::header::USER_AGENT,
-      args.user_agent.unwrap().parse().unwrap(),
-    );
-  }
-
-  if let Some(v) = args.ggl_cookie {
-    headers.insert(reqwest::header::COOKIE, v.parse().unwrap());
-  }
+  let user_agent = args.user_agent.unwrap_or_else(uagent::random);
+  headers.insert(reqwest::header::USER_AGENT, user_agent.parse().unwrap());
 
   headers.insert(
     reqwest::header::ACCEPT,
@@ -131,29 +346,218 @@ pub fn cli() {
     header_add!(headers, key, value);
   }
 
-  let mxm_api = MxmAPI::new(args.tries, args.timeout, Some(headers));
-  let track = {
-    if args.query.len() > 0 && crate::any::mxm::is_musixmatch_url(&args.query[0]) {
-      mxm_api.get_from_url(&args.query[0])
-    } else {
-      let kwds = args.query.join(" ");
+  let mut endpoints = cfg.endpoints.clone();
+  if let Some(url) = args.genius_url {
+    endpoints.genius = url;
+  }
+  if let Some(url) = args.lrclib_url {
+    endpoints.lrclib = url;
+  }
 
-      let urls = mxm_api.get_possible_links(&kwds);
-      let picked = if args.typ_url {
-        pick_url(&urls)
-      } else {
-        urls.get(0).unwrap()
-      };
+  let engines = search::chain(&args.search_engine, &endpoints, &cfg.search).unwrap_or_else(|e| {
+    macros::exit_err!("{e}");
+  });
+  let mut mxm_api = MxmAPI::new(retry.tries, args.timeout, Some(headers))
+    .retry_policy(retry)
+    .search_engines(engines)
+    .base_url(&endpoints.musixmatch);
+  if let Some(path) = cfg.cookie_jar() {
+    mxm_api = mxm_api.cookie_jar(path);
+  }
+  // Into the jar, a Cookie header would go to every site and hide the jar
+  if let Some(v) = &args.ggl_cookie {
+    mxm_api.add_cookies(v, &endpoints.google).unwrap_or_else(|e| {
+      macros::exit_err!("{e}");
+    });
+  }
+  let cached = cache.is_some() && !args.no_cache;
+  match cache {
+    Some(cache) if !args.no_cache => mxm_api = mxm_api.cache(cache),
+    _ if args.offline => {
+      macros::exit_err!("Offline mode needs the cache");
+    }
+    _ => (),
+  }
+  if let Some(Command::Artist { delay, .. }) = &args.command {
+    mxm_api = mxm_api.rate_limit(Duration::from_millis(*delay));
+  }
+  // Shared by every provider, a proxy that fails for one is left by all
+  let proxies = (args.proxy.is_some() || args.proxylist.is_some()).then(|| {
+    let mut urls: Vec<String> = args.proxy.clone().into_iter().collect();
+    if let Some(path) = &args.proxylist {
+      urls.extend(proxy::read_list(Path::new(path)).unwrap_or_else(|e| {
+        macros::exit_err!("{e}");
+      }));
+    }
+    Arc::new(ProxyPool::new(urls).unwrap_or_else(|e| {
+      macros::exit_err!("{e}");
+    }))
+  });
+  if let Some(pool) = &proxies {
+    mxm_api = mxm_api.proxies(pool.clone());
+  }
+  let mxm_api = Rc::new(mxm_api);
 
-      mxm_api.get_from_url(&picked.url)
+  if let Some(Command::Album { query, output_dir }) = &args.command {
+    album_command(
+      &mxm_api,
+      &endpoints,
+      query,
+      output_dir.as_deref(),
+      args.url_index,
+    );
+    std::process::exit(0);
+  }
+  if let Some(Command::Artist {
+    query,
+    albums_only,
+    prefetch,
+    max_pages,
+    ..
+  }) = &args.command
+  {
+    if *prefetch && !cached {
+      macros::exit_err!("--prefetch fills the cache, it can't be used without it");
     }
+    let artist_url = artist_url(&mxm_api, &endpoints, query, args.url_index).unwrap_or_else(|e| exit_with(e));
+    artist_command(&mxm_api, &artist_url, *max_pages, *albums_only, *prefetch);
+    std::process::exit(0);
+  }
+
+  // Only Musixmatch data is cached, the other providers need requests
+  let names: Vec<String> = match args.offline {
+    true => args
+      .providers
+      .iter()
+      .filter(|n| *n == MxmAPI::NAME)
+      .cloned()
+      .collect(),
+    false => args.providers.clone(),
   };
+  if names.is_empty() {
+    macros::exit_err!("Offline mode only works with the {} provider", MxmAPI::NAME);
+  }
+  let mut genius = GeniusAPI::new(&endpoints.genius, args.timeout);
+  let mut lrclib = LrclibAPI::new(&endpoints.lrclib, args.timeout);
+  let mut spotify = SpotifyAPI::new(&endpoints.spotify, args.timeout);
+  if let Some(pool) = &proxies {
+    genius = genius.proxies(pool.clone());
+    lrclib = lrclib.proxies(pool.clone());
+    spotify = spotify.proxies(pool.clone());
+  }
+  let providers = Providers::new()
+    .add(mxm_api.clone())
+    .add(Rc::new(genius))
+    .add(Rc::new(lrclib))
+    .prioritize(&names)
+    .unwrap_or_else(|e| {
+      macros::exit_err!("{e}");
+    });
+
+  let mut pick = |provider: &dyn LyricsProvider, urls: &[TrackItem]| {
+    if args.typ_url {
+      // Musixmatch pages are slow, get them all at once so broken ones show up
+      let status = (provider.name() == MxmAPI::NAME).then(|| mxm_api.prefetch(urls));
+      pick_url(provider, urls, status.as_deref())
+    } else {
+      Some(args.url_index)
+    }
+  };
+  // With fields, the query is the title
+  let link = args
+    .query
+    .first()
+    .filter(|_| fields.is_empty())
+    .and_then(|q| link::classify(q, &endpoints));
+  let track = match link {
+    Some(link) if link.kind.is_song() => providers.fetch_url(&link.url).unwrap_or_else(|| {
+      macros::exit_err!("No provider in use can fetch {}, check --providers", link.url);
+    }),
+    Some(link) if link.kind == LinkKind::SpotifyTrack => {
+      if args.offline {
+        macros::exit_err!("Spotify links are resolved by Spotify, they can't be used offline");
+      }
+      let song = spotify.resolve(&link.url).unwrap_or_else(|e| exit_with(e));
+      macros::log_inf!("Spotify track is '{}', looking for its lyrics", song.query());
+      providers.find(&song.query(), &mut pick)
+    }
+    Some(link) if link.kind == LinkKind::MxmAlbum => {
+      macros::exit_err!(
+        "{} is a {} page, get it with `lyrics album`",
+        link.url,
+        link.kind.name()
+      );
+    }
+    Some(link) => {
+      macros::exit_err!("{} is a {} page, not a song page", link.url, link.kind.name());
+    }
+    None if !fields.is_empty() => providers.find_exact(&fields),
+    None => {
+      let kwds = args.query.join(" ");
+      providers.find(&kwds, &mut pick)
+    }
+  }
+  .unwrap_or_else(|e| exit_with(e));
 
   // let track = TrackInfo::from(crate::dummy::get_json()).unwrap_or_else(|| macros::exit_err("Not able to get TrackInfo"));
 
+  if args.json {
+    match serde_json::to_string_pretty(&track) {
+      Ok(json) => println!("{json}"),
+      Err(e) => {
+        macros::exit_err!("Could not serialize the song data: {e}");
+      }
+    }
+    std::process::exit(0);
+  }
+
+  if (args.synced || args.word_synced) && !track.has_lyrics() {
+    exit_no_lyrics(&track);
+  }
+
+  if args.word_synced {
+    let lrc = lrc::to_enhanced_lrc(&track).unwrap_or_else(|| {
+      macros::exit_err!("This song has no word-synced lyrics");
+    });
+    print!("{lrc}");
+    std::process::exit(0);
+  }
+
+  if args.synced {
+    let lrc = lrc::to_lrc(&track).unwrap_or_else(|| {
+      macros::exit_err!("This song has no synced lyrics");
+    });
+    // Plain LRC, to be usable from pipes or saved as a file
+    print!("{lrc}");
+    std::process::exit(0);
+  }
+
+  let translation = args
+    .translate
+    .as_ref()
+    .filter(|_| track.has_lyrics())
+    .filter(|_| {
+      let from_mxm = track.source == MxmAPI::NAME;
+      if !from_mxm {
+        macros::log_err!("Translations are only available from {}", MxmAPI::NAME);
+      }
+      from_mxm
+    })
+    .and_then(|lang| match mxm_api.get_translation(&track.url, lang) {
+      Ok(t) if !t.is_empty() => Some(t),
+      Ok(_) => {
+        macros::log_err!("There is no '{lang}' translation for this song");
+        None
+      }
+      Err(e) => {
+        macros::log_err!("Could not get '{lang}' translation: {e}");
+        None
+      }
+    });
+
   if args.only_lyrics {
-    if !track.has_lyrics {
-      macros::exit_err!("This song has no lyrics or lyrics are not available");
+    if !track.has_lyrics() {
+      exit_no_lyrics(&track);
     }
     if args.repeat {
       println!(
@@ -162,7 +566,8 @@ pub fn cli() {
       );
     }
     // This has no custom color to use it in pipes properly
-    println!("{}", track.lyrics);
+    let lines: Vec<&str> = track.lyrics.lines().collect();
+    print_lines(&lines, translation.as_ref(), args.side_by_side, "");
     std::process::exit(0);
   }
 
@@ -173,11 +578,57 @@ pub fn cli() {
   // Defaults to all relevant info
   println!("{:img_pad$}{keyc}TITLE     : {valc}{}", "", track.name);
   println!("{:img_pad$}{keyc}ARTIST    : {valc}{}", "", track.artist);
-  println!("{:img_pad$}{keyc}ALBUM     : {valc}{}", "", track.album);
-  println!("{:img_pad$}{keyc}GENRE     : {valc}{}", "", track.genre);
-  println!("{:img_pad$}{keyc}RELEASED  : {valc}{}", "", track.released);
-  println!("{:img_pad$}{keyc}SPOTIFY   : {valc}{}", "", track.spotify);
-  println!("{:img_pad$}{keyc}MUSIXMATCH: {valc}{}", "", track.musixmatch);
+  println!(
+    "{:img_pad$}{keyc}ALBUM     : {valc}{}",
+    "",
+    track.album.as_deref().unwrap_or("Unespecified")
+  );
+  println!(
+    "{:img_pad$}{keyc}GENRE     : {valc}{}",
+    "",
+    track.genre.as_deref().unwrap_or("Unespecified")
+  );
+  println!(
+    "{:img_pad$}{keyc}RELEASED  : {valc}{}",
+    "",
+    track.released.as_deref().unwrap_or("0000-00-00")
+  );
+  println!(
+    "{:img_pad$}{keyc}DURATION  : {valc}{}",
+    "",
+    duration(track.duration)
+  );
+  println!(
+    "{:img_pad$}{keyc}ISRC      : {valc}{}",
+    "",
+    track.isrc.as_deref().unwrap_or("Unespecified")
+  );
+  println!(
+    "{:img_pad$}{keyc}WRITERS   : {valc}{}",
+    "",
+    joined(&track.writers)
+  );
+  println!(
+    "{:img_pad$}{keyc}PUBLISHERS: {valc}{}",
+    "",
+    joined(&track.publishers)
+  );
+  println!("{:img_pad$}{keyc}LANGUAGE  : {valc}{}", "", language(&track));
+  println!("{:img_pad$}{keyc}FLAGS     : {valc}{}", "", flags(&track));
+  println!(
+    "{:img_pad$}{keyc}TRACK ID  : {valc}{}",
+    "",
+    track
+      .track_id
+      .map_or("Unespecified".into(), |id| id.to_string())
+  );
+  println!(
+    "{:img_pad$}{keyc}SPOTIFY   : {valc}{}",
+    "",
+    track.spotify.as_deref().unwrap_or("Missing Spotify music ID")
+  );
+  println!("{:img_pad$}{keyc}SOURCE    : {valc}{}", "", track.source);
+  println!("{:img_pad$}{keyc}URL       : {valc}{}", "", track.url);
 
   if args.show_cover {
     let conf = viuer::Config {
@@ -190,42 +641,442 @@ pub fn cli() {
       ..Default::default()
     };
 
-    let response = reqwest::blocking::get(track.cover).expect("get album cover art");
-    let bytes = response.bytes().expect("get response bytes");
-    let img = image::load_from_memory(&bytes).expect("decode image");
-
-    viuer::print(&img, &conf).expect("print image");
+    match track.cover.as_deref().map(|c| mxm_api.get_bytes(c)) {
+      None => {
+        macros::log_err!("The song has no cover art");
+      }
+      Some(Ok(bytes)) => match image::load_from_memory(&bytes) {
+        Ok(img) => {
+          viuer::print(&img, &conf).expect("print image");
+        }
+        Err(e) => {
+          macros::log_err!("Could not decode the cover art: {e}");
+        }
+      },
+      Some(Err(e)) => {
+        macros::log_err!("Could not get the cover art: {e}");
+      }
+    }
   }
 
-  if !track.has_lyrics {
-    print!("\nLyrics are not available :(");
-    std::process::exit(0);
+  if !track.has_lyrics() {
+    println!();
+    exit_no_lyrics(&track);
   }
 
-  print!("\n{keyc}LYRICS\x1b[0m\n\n");
+  match &translation {
+    Some(t) => print!("\n{keyc}LYRICS {valc}(+{})\x1b[0m\n\n", t.lang),
+    None => print!("\n{keyc}LYRICS\x1b[0m\n\n"),
+  }
 
+  let trlc = "\x1b[38;2;139;233;253m";
   if !track.has_lyrics_struct {
-    print!("{}\n\nCopyright -> {}\n", track.lyrics, track.lyrics_copyright);
+    let lines: Vec<&str> = track.lyrics.lines().collect();
+    print_lines(&lines, translation.as_ref(), args.side_by_side, trlc);
+    print!("\n\nCopyright -> {}\n", copyright(&track));
     std::process::exit(0)
   }
-  for paragraph in track.lyrics_struct {
+  for paragraph in &track.lyrics_struct {
     println!("\x1b[38;2;189;147;249m#[section({})]\x1b[0m", paragraph.title);
-    for line in paragraph.lines {
-      print!("{}\n", line);
-    }
+    let lines: Vec<&str> = paragraph.lines.iter().map(String::as_str).collect();
+    print_lines(&lines, translation.as_ref(), args.side_by_side, trlc);
     print!("\n\n")
   }
   // For now, it has a trailing '\n'
-  print!("Copyright -> {}", track.lyrics_copyright);
+  print!("Copyright -> {}", copyright(&track));
+}
+
+/// Like `3:05`
+fn duration(secs: Option<u32>) -> String {
+  match secs {
+    Some(s) => format!("{}:{:02}", s / 60, s % 60),
+    None => "Unespecified".into(),
+  }
 }
 
-fn pick_url(urls: &Vec<TrackItem>) -> &TrackItem {
-  println!("\x1b[38;2;195;79;230mAvailable options are:\x1b[0m");
-  for i in 0..urls.len() {
+fn joined(list: &[String]) -> String {
+  match list.is_empty() {
+    true => "Unespecified".into(),
+    false => list.join(", "),
+  }
+}
+
+/// Like `English (en)`
+fn language(track: &TrackInfo) -> String {
+  let name = track.lyrics_lang.as_deref().unwrap_or("Unespecified");
+  match &track.lyrics_lang_code {
+    Some(code) => format!("{name} ({code})"),
+    None => name.into(),
+  }
+}
+
+fn copyright(track: &TrackInfo) -> &str {
+  track.lyrics_copyright.as_deref().unwrap_or("Unespecified")
+}
+
+fn flags(track: &TrackInfo) -> String {
+  let flags: Vec<&str> = [
+    (track.explicit, "explicit"),
+    (track.instrumental, "instrumental"),
+  ]
+  .into_iter()
+  .filter_map(|(set, name)| set.then_some(name))
+  .collect();
+  match flags.is_empty() {
+    true => "none".into(),
+    false => flags.join(", "),
+  }
+}
+
+/// Print lyrics lines, each one followed by (or next to) its translation,
+/// translated lines are printed using `color` (an escape sequence, or "")
+fn print_lines(lines: &[&str], translation: Option<&Translation>, side_by_side: bool, color: &str) {
+  let Some(translation) = translation else {
+    for line in lines {
+      println!("{line}");
+    }
+    return;
+  };
+
+  let reset = if color.is_empty() { "" } else { "\x1b[0m" };
+  let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+  for line in lines {
+    let translated = translation.line(line).unwrap_or_default();
+    if side_by_side {
+      println!("{line:width$}  {color}{translated}{reset}");
+      continue;
+    }
+    println!("{line}");
+    if !translated.is_empty() {
+      println!("{color}{translated}{reset}");
+    }
+  }
+}
+
+/// Tell why the song has no lyrics and exit with the code of the reason:
+/// 3 missing, 4 instrumental, 5 restricted, 6 pending
+fn exit_no_lyrics(track: &TrackInfo) -> ! {
+  let status = track.lyrics_status;
+  if status == LyricsStatus::Restricted {
+    let others: Vec<&str> = [MxmAPI::NAME, GeniusAPI::NAME, LrclibAPI::NAME]
+      .into_iter()
+      .filter(|n| *n != track.source)
+      .collect();
+    macros::log_err!(
+      "{} on {}, try another provider with --providers (like {})",
+      status.message(),
+      track.source,
+      others.join(" or ")
+    );
+  } else {
+    macros::log_err!("{}", status.message());
+  }
+
+  std::process::exit(match status {
+    LyricsStatus::Available => 0,
+    LyricsStatus::Missing => 3,
+    LyricsStatus::Instrumental => 4,
+    LyricsStatus::Restricted => 5,
+    LyricsStatus::Pending => 6,
+  })
+}
+
+/// Report an error from the data layer (and what caused it) and exit
+fn exit_with(e: ResponseErr) -> ! {
+  let mut source = e.source();
+  while let Some(cause) = source {
+    macros::log_err!("Caused by: {cause}");
+    source = cause.source();
+  }
+
+  match e {
+    ResponseErr::Captcha | ResponseErr::JsEnforcement => {
+      macros::exit_err!("{e}, try again later or pass cookies with -C");
+    }
+    ResponseErr::Timeout(_) => {
+      macros::exit_err!("{e}, try increasing the timeout with -t");
+    }
+    ResponseErr::DeadlineExceeded => {
+      macros::exit_err!("{e}, try a longer --deadline");
+    }
+    ResponseErr::Network(_) | ResponseErr::HttpStatus(_) | ResponseErr::RateLimited(_) => {
+      macros::exit_err!("Couldn't get a (valid) response from the server: {e}");
+    }
+    _ => {
+      macros::exit_err!("{e}");
+    }
+  }
+}
+
+fn cache_command(action: &CacheAction, cache: &Cache) {
+  match action {
+    CacheAction::List => {
+      let entries = cache.list();
+      if entries.is_empty() {
+        macros::log_inf!("The cache is empty");
+      }
+      let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+      for (meta, expired) in entries {
+        let age = age(now.saturating_sub(meta.stored));
+        let state = if expired { " (expired)" } else { "" };
+        println!(
+          "\x1b[38;2;255;169;140m{:<7}\x1b[38;2;195;79;230m{age:>9} ago{state}\x1b[0m  {}",
+          meta.kind.name(),
+          meta.key
+        );
+      }
+    }
+    CacheAction::Prune => {
+      macros::log_ok!("Removed {} expired entries", cache.prune());
+    }
+    CacheAction::Clear => {
+      macros::log_ok!("Removed {} entries", cache.clear());
+    }
+  }
+}
+
+/// Print (or save to `output_dir`) the lyrics of every song of the album
+/// `query` points to, in tracklist order
+fn album_command(mxm_api: &MxmAPI, endpoints: &Endpoints, query: &[String], output_dir: Option<&Path>, url_index: usize) {
+  let album_url = album_url(mxm_api, endpoints, query, url_index).unwrap_or_else(|e| exit_with(e));
+  let album = mxm_api.get_album(&album_url).unwrap_or_else(|e| exit_with(e));
+  if album.tracks.is_empty() {
+    macros::exit_err!("There are no songs in {}", album.url);
+  }
+
+  // Get every page at once, then take them in order
+  mxm_api.prefetch(&album.tracks);
+  let tracks: Vec<Result<TrackInfo, ResponseErr>> = album
+    .tracks
+    .iter()
+    .map(|item| mxm_api.get_from_url(&item.url))
+    .collect();
+  for (item, res) in album.tracks.iter().zip(&tracks) {
+    if let Err(e) = res {
+      macros::log_err!("Could not get '{}': {e}", item.desc);
+    }
+  }
+  if tracks.iter().all(Result::is_err) {
+    macros::exit_err!("Could not get any song of {}", album.url);
+  }
+
+  match output_dir {
+    Some(dir) => save_album(&album, &tracks, dir),
+    None => print_album(&album, &tracks),
+  }
+}
+
+/// The album URL itself, or the album of the song the URL (or the search
+/// result at `url_index`) is for
+fn album_url(mxm_api: &MxmAPI, endpoints: &Endpoints, query: &[String], url_index: usize) -> Result<String, ResponseErr> {
+  let track_url = match link::classify(&query[0], endpoints) {
+    Some(Link {
+      kind: LinkKind::MxmAlbum,
+      url,
+    }) => return Ok(url),
+    Some(Link {
+      kind: LinkKind::MxmTrack { .. },
+      url,
+    }) => url,
+    Some(link) => {
+      macros::exit_err!(
+        "{} is a {} page, give a Musixmatch album or song",
+        link.url,
+        link.kind.name()
+      );
+    }
+    None => {
+      let items = mxm_api.get_possible_links(&query.join(" "))?;
+      items
+        .into_iter()
+        .nth(url_index)
+        .ok_or(ResponseErr::NoEnoughData)?
+        .url
+    }
+  };
+
+  let track = mxm_api.get_from_url(&track_url)?;
+  match track.album_url {
+    Some(url) => Ok(url),
+    None => {
+      macros::exit_err!("'{}' has no album page", track.name);
+    }
+  }
+}
+
+/// The artist URL itself, or the artist of the song or album the URL (or
+/// the search result at `url_index`) is for
+fn artist_url(mxm_api: &MxmAPI, endpoints: &Endpoints, query: &[String], url_index: usize) -> Result<String, ResponseErr> {
+  if let Some(link) = link::classify(&query[0], endpoints) {
+    match link.mxm_artist() {
+      Some(url) => return Ok(url),
+      None => {
+        macros::exit_err!(
+          "{} is a {} page, give a Musixmatch artist, album or song",
+          link.url,
+          link.kind.name()
+        );
+      }
+    }
+  }
+
+  let items = mxm_api.get_possible_links(&query.join(" "))?;
+  let item = items.get(url_index).ok_or(ResponseErr::NoEnoughData)?;
+  link::classify(&item.url, endpoints)
+    .and_then(|l| l.mxm_artist())
+    .ok_or(ResponseErr::InvalidUrl(item.url.clone()))
+}
+
+/// List the albums (and songs) of the artist at `url`, getting every song
+/// into the cache if `prefetch`
+fn artist_command(mxm_api: &MxmAPI, url: &str, max_pages: u32, albums_only: bool, prefetch: bool) {
+  let artist = mxm_api
+    .get_artist(url, max_pages)
+    .unwrap_or_else(|e| exit_with(e));
+  println!("{}", artist.name);
+  println!("{}", artist.url);
+
+  let (mut with_lyrics, mut without_lyrics, mut failed) = (0, 0, 0);
+  for item in &artist.albums {
+    println!(
+      "\n{} ({})",
+      item.name,
+      item.released.as_deref().unwrap_or("0000-00-00")
+    );
+    if albums_only {
+      continue;
+    }
+
+    let album = match mxm_api.get_album(&item.url) {
+      Ok(album) => album,
+      Err(e) => {
+        macros::log_err!("Could not get the songs of '{}': {e}", item.name);
+        continue;
+      }
+    };
+    let status = match prefetch {
+      true => mxm_api.prefetch(&album.tracks),
+      false => Vec::new(),
+    };
+    for (i, track) in album.tracks.iter().enumerate() {
+      match status.get(i) {
+        Some(Ok(true)) => with_lyrics += 1,
+        Some(Ok(false)) => without_lyrics += 1,
+        Some(Err(e)) => {
+          macros::log_err!("Could not get '{}': {e}", track.desc);
+          failed += 1;
+        }
+        None => (),
+      }
+      println!("  {}. {}", i + 1, track.desc);
+    }
+  }
+
+  if prefetch {
+    macros::log_ok!("Cached {with_lyrics} songs with lyrics and {without_lyrics} without, {failed} failed");
+  }
+}
+
+/// One plain document (to use it in pipes), a header and every song lyrics
+fn print_album(album: &AlbumInfo, tracks: &[Result<TrackInfo, ResponseErr>]) {
+  println!(
+    "{} - {} ({})",
+    album.name,
+    album.artist,
+    album.released.as_deref().unwrap_or("0000-00-00")
+  );
+  println!("{}", album.url);
+  for (i, (item, res)) in album.tracks.iter().zip(tracks).enumerate() {
+    println!("\n{}. {}", i + 1, item.desc);
+    match res {
+      Ok(track) if track.has_lyrics() => println!("{}", track.lyrics.trim_end()),
+      Ok(track) => println!("{}", track.lyrics_status.message()),
+      Err(_) => println!("Could not get the lyrics :("),
+    }
+  }
+}
+
+/// Save the lyrics of each song to `<number> - <title>.txt` in `dir`
+fn save_album(album: &AlbumInfo, tracks: &[Result<TrackInfo, ResponseErr>], dir: &Path) {
+  if let Err(e) = std::fs::create_dir_all(dir) {
+    macros::exit_err!("Could not create {}: {e}", dir.display());
+  }
+
+  let mut saved = 0;
+  for (i, (item, res)) in album.tracks.iter().zip(tracks).enumerate() {
+    let Ok(track) = res else {
+      continue;
+    };
+    if !track.has_lyrics() {
+      macros::log_inf!("'{}': {}", item.desc, track.lyrics_status.message());
+      continue;
+    }
+    // No path separators nor characters some file systems reject
+    let name: String = item
+      .desc
+      .chars()
+      .map(|c| if r#"/\:*?"<>|"#.contains(c) { '_' } else { c })
+      .collect();
+    let path = dir.join(format!("{:02} - {}.txt", i + 1, name.trim()));
+    match std::fs::write(&path, format!("{}\n", track.lyrics.trim_end())) {
+      Ok(()) => saved += 1,
+      Err(e) => {
+        macros::log_err!("Could not write {}: {e}", path.display());
+      }
+    }
+  }
+  macros::log_ok!(
+    "Saved {saved} of {} songs to {}",
+    album.tracks.len(),
+    dir.display()
+  );
+}
+
+/// Short human form of a number of seconds, like `3h`
+fn age(secs: u64) -> String {
+  match secs {
+    0..60 => format!("{secs}s"),
+    60..3600 => format!("{}m", secs / 60),
+    3600..86400 => format!("{}h", secs / 3600),
+    _ => format!("{}d", secs / 86400),
+  }
+}
+
+/// `status` tells, for each URL, whether its page has lyrics or why it failed
+fn pick_url(provider: &dyn LyricsProvider, urls: &[TrackItem], status: Option<&[Result<bool, String>]>) -> Option<usize> {
+  if urls.is_empty() {
+    return None;
+  }
+
+  println!(
+    "\x1b[38;2;195;79;230mAvailable options from {} are:\x1b[0m",
+    provider.name()
+  );
+  for (i, item) in urls.iter().enumerate() {
     println!(
       "  {} \x1b[38;2;255;169;140m-> \x1b[38;2;255;232;184m{}\n    \x1b[38;2;195;79;230mAt: \x1b[38;2;189;147;249m{}\x1b[0m",
-      i, urls[i].desc, urls[i].url
+      i, item.desc, item.url
     );
+    if let Some(score) = item.score {
+      println!(
+        "    \x1b[38;2;195;79;230mMatch: \x1b[38;2;189;147;249m{:.0}%\x1b[0m",
+        score * 100.0
+      );
+    }
+    if !item.translations.is_empty() {
+      println!(
+        "    \x1b[38;2;195;79;230mTranslations: \x1b[38;2;189;147;249m{}\x1b[0m",
+        item.translations.join(", ")
+      );
+    }
+    match status.and_then(|s| s.get(i)) {
+      Some(Ok(true)) => println!("    \x1b[38;2;195;79;230mStatus: \x1b[38;2;80;250;123mLyrics found\x1b[0m"),
+      Some(Ok(false)) => println!("    \x1b[38;2;195;79;230mStatus: \x1b[38;2;255;184;108mNo lyrics\x1b[0m"),
+      Some(Err(e)) => println!("    \x1b[38;2;195;79;230mStatus: \x1b[38;2;255;85;85m{e}\x1b[0m"),
+      None => (),
+    }
   }
   print!("\x1b[38;2;195;79;230mSelect one from above:\x1b[0m ");
   std::io::stdout().flush().unwrap();
@@ -241,7 +1092,8 @@ fn pick_url(urls: &Vec<TrackItem>) -> &TrackItem {
 
   let idx: usize = (idx[0] - 48) as usize;
 
-  urls.get(idx).unwrap_or_else(|| {
+  if idx >= urls.len() {
     macros::exit_err!("Index {idx} is out of bounds");
-  })
+  }
+  Some(idx)
 }
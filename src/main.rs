@@ -4,7 +4,7 @@ mod cli;
 
 fn main() {
   let exec_name: String = std::env::args()
-    .nth(0)
+    .next()
     .unwrap()
     .split(std::path::MAIN_SEPARATOR)
     .collect::<Vec<&str>>()[..]
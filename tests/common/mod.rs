@@ -0,0 +1,266 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Recorded page served for a request path (query string ignored)
+pub struct Route {
+  pub path: &'static str,
+  pub status: u16,
+  pub fixture: &'static str,
+  /// Extra response header lines
+  pub headers: Vec<&'static str>,
+  /// Requests answered with another status (and no body) before the fixture
+  pub failures: Vec<(u16, &'static str)>,
+  pub hits: AtomicUsize,
+}
+
+impl Route {
+  pub fn ok(path: &'static str, fixture: &'static str) -> Self {
+    Self {
+      path,
+      status: 200,
+      fixture,
+      headers: Vec::new(),
+      failures: Vec::new(),
+      hits: AtomicUsize::new(0),
+    }
+  }
+
+  /// Answer the next request with `status` and the `extra` header line
+  /// (may be empty), then the fixture
+  pub fn fail(mut self, status: u16, extra: &'static str) -> Self {
+    self.failures.push((status, extra));
+    self
+  }
+
+  pub fn header(mut self, line: &'static str) -> Self {
+    self.headers.push(line);
+    self
+  }
+}
+
+/// A request received by the server
+pub struct Request {
+  pub method: String,
+  /// Path with its query string
+  pub target: String,
+  /// Form data of POST requests
+  pub body: String,
+  pub cookie: Option<String>,
+  /// Host asked for, when the server was used as a proxy
+  pub proxied_host: Option<String>,
+}
+
+/// Local HTTP server standing in for every remote site, so the whole
+/// pipeline runs without internet access
+pub struct FixtureServer {
+  pub base: String,
+  pub requests: Arc<Mutex<Vec<Request>>>,
+  /// Cookie jar file of the runs against this server
+  pub cookie_jar: PathBuf,
+  /// Cache directory of the runs against this server
+  pub cache_dir: PathBuf,
+}
+
+impl FixtureServer {
+  pub fn start(routes: Vec<Route>) -> Self {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind fixture server");
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+
+    let log = requests.clone();
+    thread::spawn(move || {
+      for stream in listener.incoming().flatten() {
+        serve(stream, &routes, &log);
+      }
+    });
+
+    let prefix = format!(
+      "lyrics-test-{}-{}",
+      std::process::id(),
+      base.rsplit(':').next().unwrap()
+    );
+    let cookie_jar = std::env::temp_dir().join(format!("{prefix}.json"));
+    let cache_dir = std::env::temp_dir().join(format!("{prefix}-cache"));
+    let _ = std::fs::remove_file(&cookie_jar);
+    let _ = std::fs::remove_dir_all(&cache_dir);
+
+    Self {
+      base,
+      requests,
+      cookie_jar,
+      cache_dir,
+    }
+  }
+
+  /// Run the binary with every endpoint pointing to this server
+  pub fn run(&self, args: &[&str]) -> Output {
+    self.command(args).output().expect("run lyrics binary")
+  }
+
+  /// Like `run`, with extra environment variables (overriding the endpoints)
+  pub fn run_env(&self, args: &[&str], env: &[(&str, &str)]) -> Output {
+    self
+      .command(args)
+      .envs(env.iter().copied())
+      .output()
+      .expect("run lyrics binary")
+  }
+
+  /// Like `run`, writing `input` to the program stdin
+  pub fn run_with_input(&self, args: &[&str], input: &str) -> Output {
+    let mut child = self
+      .command(args)
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .stderr(Stdio::piped())
+      .spawn()
+      .expect("run lyrics binary");
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    child.wait_with_output().expect("wait for lyrics binary")
+  }
+
+  fn command(&self, args: &[&str]) -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_lyrics"));
+    for name in [
+      "GOOGLE",
+      "BING",
+      "DUCKDUCKGO",
+      "MUSIXMATCH",
+      "GENIUS",
+      "LRCLIB",
+      "SPOTIFY",
+    ] {
+      cmd.env(format!("LYRICS_{name}_URL"), &self.base);
+    }
+    cmd
+      .env("LYRICS_CONFIG", fixture_path("config.json"))
+      .env("LYRICS_COOKIE_JAR", &self.cookie_jar)
+      .env("LYRICS_CACHE_DIR", &self.cache_dir)
+      .args(args);
+    cmd
+  }
+
+  pub fn requested(&self, path: &str) -> bool {
+    self.request_count(path) > 0
+  }
+
+  pub fn request_count(&self, path: &str) -> usize {
+    self.cookies_sent(path).len()
+  }
+
+  /// Hosts of the requests received as a proxy
+  pub fn proxied_hosts(&self) -> Vec<String> {
+    self
+      .requests
+      .lock()
+      .unwrap()
+      .iter()
+      .filter_map(|r| r.proxied_host.clone())
+      .collect()
+  }
+
+  /// Cookie header of every request to `path`, in order
+  pub fn cookies_sent(&self, path: &str) -> Vec<Option<String>> {
+    self
+      .requests
+      .lock()
+      .unwrap()
+      .iter()
+      .filter(|r| r.target.split('?').next() == Some(path))
+      .map(|r| r.cookie.clone())
+      .collect()
+  }
+}
+
+impl Drop for FixtureServer {
+  fn drop(&mut self) {
+    let _ = std::fs::remove_file(&self.cookie_jar);
+    let _ = std::fs::remove_dir_all(&self.cache_dir);
+  }
+}
+
+pub fn fixture_path(name: &str) -> PathBuf {
+  PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+    .join("tests")
+    .join("fixtures")
+    .join(name)
+}
+
+pub fn stdout(out: &Output) -> String {
+  String::from_utf8_lossy(&out.stdout).into_owned()
+}
+
+fn serve(mut stream: TcpStream, routes: &[Route], log: &Mutex<Vec<Request>>) {
+  let mut reader = BufReader::new(stream.try_clone().unwrap());
+  let mut request_line = String::new();
+  if reader.read_line(&mut request_line).is_err() {
+    return;
+  }
+  let mut cookie = None;
+  let mut length = 0;
+  let mut line = String::new();
+  while reader.read_line(&mut line).is_ok_and(|n| n > 2) {
+    if let Some((name, value)) = line.split_once(':') {
+      if name.eq_ignore_ascii_case("cookie") {
+        cookie = Some(value.trim().to_string());
+      } else if name.eq_ignore_ascii_case("content-length") {
+        length = value.trim().parse().unwrap_or_default();
+      }
+    }
+    line.clear();
+  }
+  let mut body = vec![0; length];
+  let _ = reader.read_exact(&mut body);
+
+  let method = request_line
+    .split_whitespace()
+    .next()
+    .unwrap_or("GET")
+    .to_string();
+  let target = request_line.split_whitespace().nth(1).unwrap_or("/");
+  // Requests sent through a proxy carry the whole URL, like http://host/path
+  let (proxied_host, target) = match target.strip_prefix("http://") {
+    Some(rest) => {
+      let at = rest.find('/').unwrap_or(rest.len());
+      (Some(rest[..at].to_string()), rest[at..].to_string())
+    }
+    None => (None, target.to_string()),
+  };
+  let path = target
+    .split('?')
+    .next()
+    .unwrap_or("/")
+    .trim_end_matches('/')
+    .to_string();
+  log.lock().unwrap().push(Request {
+    method,
+    target,
+    body: String::from_utf8_lossy(&body).into_owned(),
+    cookie,
+    proxied_host,
+  });
+
+  let (status, body, extra) = match routes.iter().find(|r| r.path == path) {
+    Some(r) => match r.failures.get(r.hits.fetch_add(1, Ordering::SeqCst)) {
+      Some((status, "")) => (*status, Vec::new(), String::new()),
+      Some((status, extra)) => (*status, Vec::new(), format!("{extra}\r\n")),
+      None => (
+        r.status,
+        std::fs::read(fixture_path(r.fixture)).expect("read fixture"),
+        r.headers.iter().map(|h| format!("{h}\r\n")).collect(),
+      ),
+    },
+    None => (404, b"Not Found".to_vec(), String::new()),
+  };
+  let head = format!(
+    "HTTP/1.1 {status} Fixture\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n{extra}\r\n",
+    body.len()
+  );
+  let _ = stream.write_all(head.as_bytes());
+  let _ = stream.write_all(&body);
+}
@@ -0,0 +1,1363 @@
+mod common;
+
+use common::{fixture_path, stdout, FixtureServer, Route};
+use std::time::{Duration, Instant};
+
+const TRACK: &str = "/lyrics/Linkin-Park/Numb";
+const ALBUM: &str = "/album/Linkin-Park/Meteora";
+
+fn musixmatch_site() -> FixtureServer {
+  FixtureServer::start(vec![
+    Route::ok("/search", "google_results.html"),
+    Route::ok(TRACK, "mxm_track.html"),
+    Route::ok(
+      "/lyrics/Linkin-Park/Numb/translation/es",
+      "mxm_translation.html",
+    ),
+    Route::ok(ALBUM, "mxm_album.html"),
+    Route::ok("/artist/Linkin-Park/albums", "mxm_artist.html"),
+    Route::ok("/artist/Linkin-Park/albums/2", "mxm_artist_2.html"),
+  ])
+}
+
+#[test]
+fn search_pick_and_render() {
+  let server = musixmatch_site();
+  let out = server.run(&["numb", "linkin", "park"]);
+  let text = stdout(&out);
+
+  assert!(out.status.success(), "{text}");
+  assert!(server.requested("/search"));
+  assert!(server.requested(TRACK));
+  assert!(text.contains("Numb"));
+  assert!(text.contains("Linkin Park"));
+  assert!(text.contains("Meteora"));
+  assert!(text.contains("Rock"));
+  assert!(text.contains("2003-03-25"));
+  assert!(text.contains("https://open.spotify.com/track/2nLtzopw4rPReszdYBJU6h"));
+  assert!(text.contains("https://www.musixmatch.com/lyrics/Linkin-Park/Numb"));
+  assert!(text.contains("I've become so numb, I can't feel you there"));
+  for detail in [
+    "3:07",
+    "USWB10300474",
+    "Chester Bennington, Mike Shinoda",
+    "Zomba Songs",
+    "English (en)",
+  ] {
+    assert!(text.contains(detail), "{detail} missing: {text}");
+  }
+}
+
+#[test]
+fn song_data_as_json() {
+  let server = musixmatch_site();
+  let out = server.run(&["-j", "numb"]);
+  let json: serde_json::Value = serde_json::from_slice(&out.stdout).expect("JSON output");
+
+  assert_eq!(json["name"], "Numb");
+  assert_eq!(json["duration"], 187);
+  assert_eq!(json["isrc"], "USWB10300474");
+  assert_eq!(json["explicit"], false);
+  assert_eq!(json["instrumental"], false);
+  assert_eq!(json["writers"][1], "Mike Shinoda");
+  assert_eq!(json["publishers"][0], "Zomba Songs");
+  assert_eq!(json["lyrics_lang_code"], "en");
+  assert_eq!(json["track_id"], 1);
+  assert_eq!(json["source"], "musixmatch");
+  assert_eq!(json["lyrics_status"], "available");
+  assert_eq!(json["released"], "2003-03-25");
+  // Times are plain milliseconds
+  assert_eq!(json["synced"][0]["time"], 10500);
+  assert_eq!(json["rich_synced"][0]["start"], 10500);
+  assert_eq!(json["rich_synced"][0]["end"], 14500);
+  assert_eq!(
+    json["rich_synced"][0]["words"][2],
+    serde_json::json!([400, "tired"])
+  );
+}
+
+#[test]
+fn song_data_as_json_leaves_unknown_fields_null() {
+  let server = FixtureServer::start(vec![Route::ok(
+    "/lyrics/Artist/Instrumental",
+    "mxm_instrumental.html",
+  )]);
+  let out = server.run(&["-j", "https://www.musixmatch.com/lyrics/Artist/Instrumental"]);
+  let json: serde_json::Value = serde_json::from_slice(&out.stdout).expect("JSON output");
+
+  assert_eq!(json["lyrics_status"], "instrumental");
+  assert_eq!(json["lyrics"], "");
+  for field in ["lyrics_lang", "lyrics_copyright", "synced", "rich_synced"] {
+    assert!(json[field].is_null(), "{field}: {}", json[field]);
+  }
+}
+
+#[test]
+fn missing_lyrics_tell_why() {
+  let server = FixtureServer::start(vec![
+    Route::ok("/lyrics/Artist/Restricted", "mxm_restricted.html"),
+    Route::ok("/lyrics/Artist/Instrumental", "mxm_instrumental.html"),
+    Route::ok("/lyrics/Artist/Pending", "mxm_pending.html"),
+  ]);
+  for (song, code, message) in [
+    ("Restricted", 5, "restricted in your region"),
+    ("Instrumental", 4, "instrumental"),
+    ("Pending", 6, "pending review"),
+  ] {
+    let url = format!("https://www.musixmatch.com/lyrics/Artist/{song}");
+    for args in [vec!["-l", url.as_str()], vec![url.as_str()]] {
+      let out = server.run(&args);
+      let err = String::from_utf8_lossy(&out.stderr);
+
+      assert_eq!(out.status.code(), Some(code), "{song}: {err}");
+      assert!(err.contains(message), "{song}: {err}");
+    }
+  }
+
+  let out = server.run(&["-l", "https://www.musixmatch.com/lyrics/Artist/Restricted"]);
+  let err = String::from_utf8_lossy(&out.stderr);
+  assert!(err.contains("--providers"), "{err}");
+  assert!(err.contains("genius"), "{err}");
+}
+
+#[test]
+fn genius_page_sections_and_text() {
+  let server = FixtureServer::start(vec![Route::ok("/Linkin-park-numb-lyrics", "genius_song.html")]);
+  let out = server.run(&["-j", "https://genius.com/Linkin-park-numb-lyrics"]);
+  let json: serde_json::Value = serde_json::from_slice(&out.stdout).expect("JSON output");
+
+  assert_eq!(json["source"], "genius");
+  assert_eq!(json["name"], "Numb");
+  assert_eq!(json["artist"], "Linkin Park");
+  assert_eq!(json["cover"], "https://images.genius.com/numb.1000x1000x1.jpg");
+  assert_eq!(json["has_lyrics_struct"], true);
+
+  let sections = &json["lyrics_struct"];
+  assert_eq!(sections[0]["title"], "Verse 1: Chester Bennington");
+  // Annotated lines keep their text, without the link
+  assert_eq!(
+    sections[0]["lines"][0],
+    "I'm tired of being what you want me to be"
+  );
+  // Containers continue the section they split
+  assert_eq!(sections[1]["title"], "Chorus: Chester Bennington");
+  assert_eq!(
+    sections[1]["lines"],
+    serde_json::json!([
+      "I've become so numb, I can't feel you there",
+      "Become so tired, so much more aware"
+    ])
+  );
+  assert_eq!(sections[2]["title"], "Outro: Chester Bennington");
+
+  // Page widgets inside the containers are not lyrics
+  let lyrics = json["lyrics"].as_str().unwrap();
+  for widget in ["Numb Lyrics", "Contributors", "Embed", "["] {
+    assert!(!lyrics.contains(widget), "{widget}: {lyrics}");
+  }
+}
+
+#[test]
+fn genius_instrumental_is_flagged() {
+  let server = FixtureServer::start(vec![Route::ok(
+    "/Artist-instrumental-lyrics",
+    "genius_instrumental.html",
+  )]);
+  let out = server.run(&["-j", "https://genius.com/Artist-instrumental-lyrics"]);
+  let json: serde_json::Value = serde_json::from_slice(&out.stdout).expect("JSON output");
+
+  assert_eq!(json["lyrics_status"], "instrumental");
+  assert_eq!(json["instrumental"], true);
+}
+
+#[test]
+fn missing_lyrics_fall_back_to_genius() {
+  let server = FixtureServer::start(vec![
+    Route::ok("/search", "google_results.html"),
+    Route::ok(TRACK, "mxm_missing.html"),
+    Route::ok("/api/search/song", "genius_search.json"),
+    Route::ok("/Linkin-park-numb-lyrics", "genius_song.html"),
+  ]);
+  let out = server.run(&["--providers", "musixmatch,genius", "-l", "numb"]);
+  let err = String::from_utf8_lossy(&out.stderr);
+
+  assert!(out.status.success(), "{err}");
+  assert!(err.contains("musixmatch: Lyrics are not available"), "{err}");
+  assert!(server.requested(TRACK));
+  assert!(server.requested("/Linkin-park-numb-lyrics"));
+  assert!(stdout(&out).contains("I've become so numb, I can't feel you there"));
+}
+
+fn lrclib_site() -> FixtureServer {
+  FixtureServer::start(vec![
+    Route::ok("/api/search", "lrclib_search.json"),
+    Route::ok("/api/get", "lrclib_get.json"),
+    Route::ok("/api/get/101", "lrclib_get.json"),
+  ])
+}
+
+#[test]
+fn lrclib_search_as_lrc() {
+  let server = lrclib_site();
+  let out = server.run(&["--providers", "lrclib", "-S", "numb", "linkin", "park"]);
+  let text = stdout(&out);
+
+  assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+  // The studio version is ranked over the live one given first
+  assert!(server.requested("/api/get/101"));
+  assert!(text.contains("[al:Meteora]"), "{text}");
+  // Metadata tags are not lines, a line with two timestamps is sung twice
+  assert_eq!(text.matches("[ar:").count(), 1, "{text}");
+  assert!(!text.contains("[by:"), "{text}");
+  let lines: Vec<&str> = text.lines().filter(|l| l.starts_with("[0")).collect();
+  assert_eq!(
+    lines,
+    [
+      "[00:10.50]I'm tired of being what you want me to be",
+      "[00:22.30]I've become so numb, I can't feel you there",
+      "[00:25.00]Become so tired, so much more aware",
+      "[01:05.10]I've become so numb, I can't feel you there",
+    ]
+  );
+}
+
+#[test]
+fn lrclib_signature_lookup() {
+  let server = lrclib_site();
+  let out = server.run(&[
+    "--providers",
+    "lrclib",
+    "--artist",
+    "Linkin Park",
+    "--title",
+    "Numb",
+    "--duration",
+    "187",
+    "-l",
+  ]);
+
+  assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+  assert!(!server.requested("/api/search"));
+  assert!(stdout(&out).contains("I've become so numb"));
+  let requests = server.requests.lock().unwrap();
+  let get = requests
+    .iter()
+    .find(|r| r.target.starts_with("/api/get?"))
+    .unwrap();
+  for param in ["track_name=Numb", "artist_name=Linkin+Park", "duration=187"] {
+    assert!(get.target.contains(param), "{param}: {}", get.target);
+  }
+}
+
+#[test]
+fn lrclib_results_of_other_lengths_are_dropped() {
+  let server = lrclib_site();
+  let out = server.run(&[
+    "--providers",
+    "lrclib",
+    "--title",
+    "Numb",
+    "--duration",
+    "188",
+    "-l",
+  ]);
+
+  assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+  assert!(server.requested("/api/get/101"));
+  // The live version is 241 seconds long, it is never fetched
+  assert!(!server.requested("/api/get/102"));
+
+  // Nothing that long
+  let out = server.run(&[
+    "--providers",
+    "lrclib",
+    "--title",
+    "Numb",
+    "--duration",
+    "300",
+    "-l",
+  ]);
+  assert!(!out.status.success());
+  assert_eq!(server.request_count("/api/get/101"), 1);
+}
+
+#[test]
+fn lrclib_fields_without_title_are_free_text() {
+  let server = lrclib_site();
+  let out = server.run(&["--providers", "lrclib", "--artist", "Linkin Park", "-l"]);
+
+  assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+  let requests = server.requests.lock().unwrap();
+  let search = requests
+    .iter()
+    .find(|r| r.target.starts_with("/api/search"))
+    .unwrap();
+  assert!(search.target.contains("q=Linkin+Park"), "{}", search.target);
+  assert!(!search.target.contains("track_name"), "{}", search.target);
+}
+
+#[test]
+fn url_index_picks_another_result() {
+  let server = musixmatch_site();
+  let out = server.run(&["-i", "1", "numb"]);
+
+  // The translation result is folded into the first one, so index 1 is the
+  // Jay-Z mashup, which has no fixture
+  assert!(!out.status.success());
+  assert!(server.requested("/lyrics/Linkin-Park-feat-Jay-Z/Numb-Encore"));
+}
+
+#[test]
+fn best_match_is_picked_over_other_versions() {
+  let server = FixtureServer::start(vec![
+    Route::ok("/search", "google_versions.html"),
+    Route::ok(TRACK, "mxm_track.html"),
+  ]);
+  let out = server.run(&["-l", "numb", "linkin", "park"]);
+
+  assert!(out.status.success(), "{}", stdout(&out));
+  assert_eq!(server.request_count(TRACK), 1);
+  assert_eq!(server.requests.lock().unwrap().len(), 2);
+
+  // Unless the query asks for that version
+  let out = server.run(&["-l", "--no-cache", "numb", "linkin", "park", "live"]);
+  assert!(!out.status.success());
+  assert!(server.requested("/lyrics/Linkin-Park/Numb-Live-at-Milton-Keynes"));
+}
+
+#[test]
+fn candidates_are_prefetched_before_picking() {
+  let server = musixmatch_site();
+  let out = server.run_with_input(&["-a", "-l", "numb"], "0");
+  let text = stdout(&out);
+
+  assert!(out.status.success(), "{text}");
+  assert!(text.contains("Lyrics found"));
+  assert!(text.contains("Match: ") && text.contains("100%"), "{text}");
+  assert!(text.contains("404 Not Found"));
+  assert!(server.requested("/lyrics/Linkin-Park-feat-Jay-Z/Numb-Encore"));
+  // The picked page is not requested again
+  assert_eq!(server.request_count(TRACK), 1);
+  assert!(text.contains("I'm tired of being what you want me to be"));
+}
+
+#[test]
+fn public_url_is_fetched_from_the_configured_base() {
+  let server = musixmatch_site();
+  let out = server.run(&["-l", "https://www.musixmatch.com/lyrics/Linkin-Park/Numb"]);
+  let text = stdout(&out);
+
+  assert!(out.status.success(), "{text}");
+  assert!(!server.requested("/search"));
+  assert!(text.contains("I'm tired of being what you want me to be"));
+  assert!(!text.contains("Meteora"));
+}
+
+#[test]
+fn url_variants_are_normalized() {
+  for url in [
+    "https://es.musixmatch.com/lyrics/Linkin-Park/Numb/translation/spanish",
+    "www.musixmatch.com/es/lyrics/Linkin-Park/Numb/embed?utm_source=x#top",
+    "HTTP://MUSIXMATCH.COM/lyrics/Linkin-Park/Numb/",
+  ] {
+    let server = musixmatch_site();
+    let out = server.run(&["-l", url]);
+
+    assert!(out.status.success(), "{url}: {}", stdout(&out));
+    assert!(!server.requested("/search"), "{url}");
+    assert_eq!(server.request_count(TRACK), 1, "{url}");
+  }
+}
+
+#[test]
+fn short_text_is_a_query() {
+  let server = musixmatch_site();
+  let out = server.run(&["-l", "ht"]);
+
+  assert!(out.status.success(), "{}", stdout(&out));
+  assert!(server.requested("/search"));
+}
+
+#[test]
+fn non_song_links_are_reported() {
+  let server = musixmatch_site();
+  for (url, kind) in [
+    (
+      "https://www.musixmatch.com/album/Linkin-Park/Meteora",
+      "Musixmatch album",
+    ),
+    (
+      "https://www.musixmatch.com/artist/Linkin-Park/albums",
+      "Musixmatch artist",
+    ),
+  ] {
+    let out = server.run(&[url]);
+    let err = String::from_utf8_lossy(&out.stderr);
+
+    assert!(!out.status.success());
+    assert!(err.contains(kind), "{url}: {err}");
+  }
+  assert!(server.requests.lock().unwrap().is_empty());
+}
+
+#[test]
+fn spotify_links_are_resolved_to_lyrics() {
+  for link in [
+    "https://open.spotify.com/intl-es/track/2nLtzopw4rPReszdYBJU6h?si=1",
+    "spotify:track:2nLtzopw4rPReszdYBJU6h",
+  ] {
+    let server = FixtureServer::start(vec![
+      Route::ok("/embed/track/2nLtzopw4rPReszdYBJU6h", "spotify_embed.html"),
+      Route::ok("/search", "google_results.html"),
+      Route::ok(TRACK, "mxm_track.html"),
+    ]);
+    let out = server.run(&["--providers", "musixmatch", "-l", link]);
+
+    assert!(out.status.success(), "{link}: {}", stdout(&out));
+    let requests = server.requests.lock().unwrap();
+    let search = requests
+      .iter()
+      .find(|r| r.target.starts_with("/search"))
+      .unwrap();
+    assert!(search.target.contains("Linkin+Park+Numb"), "{}", search.target);
+    assert!(stdout(&out).contains("I've become so numb"));
+  }
+}
+
+#[test]
+fn spotify_oembed_is_used_when_the_embed_page_fails() {
+  let server = FixtureServer::start(vec![
+    Route::ok("/oembed", "spotify_oembed.json"),
+    Route::ok("/search", "google_results.html"),
+    Route::ok(TRACK, "mxm_track.html"),
+  ]);
+  let out = server.run(&[
+    "--providers",
+    "musixmatch",
+    "-l",
+    "https://open.spotify.com/track/2nLtzopw4rPReszdYBJU6h",
+  ]);
+
+  assert!(out.status.success(), "{}", stdout(&out));
+  assert!(server.requested("/embed/track/2nLtzopw4rPReszdYBJU6h"));
+  let requests = server.requests.lock().unwrap();
+  let oembed = requests
+    .iter()
+    .find(|r| r.target.starts_with("/oembed"))
+    .unwrap();
+  assert!(oembed.target.contains("2nLtzopw4rPReszdYBJU6h"));
+}
+
+#[test]
+fn fields_build_the_query_and_check_the_song() {
+  let server = musixmatch_site();
+  let out = server.run(&[
+    "--providers",
+    "musixmatch",
+    "--artist",
+    "Linkin Park",
+    "--title",
+    "Numb",
+    "--album",
+    "Meteora",
+    "--isrc",
+    "uswb10300474",
+    "--duration",
+    "186",
+    "-l",
+  ]);
+
+  assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+  assert!(stdout(&out).contains("I've become so numb"));
+  let requests = server.requests.lock().unwrap();
+  let search = requests
+    .iter()
+    .find(|r| r.target.starts_with("/search"))
+    .unwrap();
+  assert!(search.target.contains("Linkin+Park+Numb"), "{}", search.target);
+}
+
+#[test]
+fn isrc_alone_is_searched() {
+  let server = musixmatch_site();
+  let out = server.run(&["--providers", "musixmatch", "--isrc", "USWB10300474", "-l"]);
+
+  assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+  assert!(stdout(&out).contains("I've become so numb"));
+  let requests = server.requests.lock().unwrap();
+  let search = requests
+    .iter()
+    .find(|r| r.target.starts_with("/search"))
+    .unwrap();
+  assert!(search.target.contains("USWB10300474"), "{}", search.target);
+}
+
+#[test]
+fn fields_reject_other_songs() {
+  let server = musixmatch_site();
+  let out = server.run(&[
+    "--providers",
+    "musixmatch",
+    "--artist",
+    "Linkin Park",
+    "--isrc",
+    "GBAYE0300001",
+    "-l",
+    "numb",
+  ]);
+  let err = String::from_utf8_lossy(&out.stderr);
+
+  assert!(!out.status.success());
+  assert!(err.contains("ISRC is USWB10300474"), "{err}");
+  assert!(!stdout(&out).contains("I've become so numb"));
+
+  // Checks alone can't be searched, and there is nothing to pick from
+  let out = server.run(&["--duration", "187"]);
+  assert!(String::from_utf8_lossy(&out.stderr).contains("give a title, artist, album or ISRC"));
+  let out = server.run(&["-a", "--artist", "Linkin Park", "numb"]);
+  assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn synced_lyrics_as_lrc() {
+  let server = musixmatch_site();
+  let text = stdout(&server.run(&["-S", "numb"]));
+
+  assert!(text.contains("[ti:Numb]"));
+  assert!(text.contains("[length:03:07]"));
+  assert!(text.contains("[ar:Linkin Park]"));
+  assert!(text.contains("[00:10.50]I'm tired of being what you want me to be"));
+  assert!(text.contains("[00:19.00]I've become so numb, I can't feel you there"));
+}
+
+#[test]
+fn word_synced_lyrics_as_enhanced_lrc() {
+  let server = musixmatch_site();
+  let text = stdout(&server.run(&["-W", "numb"]));
+
+  assert!(text.contains("[00:10.50]<00:10.50>I'm"));
+  assert!(text.contains("<00:10.90>tired"));
+}
+
+#[test]
+fn translation_next_to_the_original() {
+  let server = musixmatch_site();
+  let text = stdout(&server.run(&["-l", "-x", "es", "numb"]));
+
+  assert!(server.requested("/lyrics/Linkin-Park/Numb/translation/es"));
+  assert!(text.contains("I've become so numb, I can't feel you there"));
+  assert!(text.contains("Me he vuelto tan insensible, no puedo sentirte allí"));
+  assert!(!text.contains("Je suis fatigué"));
+}
+
+#[test]
+fn basic_google_page_redirects_are_followed() {
+  let server = FixtureServer::start(vec![
+    Route::ok("/search", "google_basic.html"),
+    Route::ok(TRACK, "mxm_track.html"),
+  ]);
+  let out = server.run_with_input(&["-a", "--providers", "musixmatch", "-l", "numb"], "0");
+  let text = stdout(&out);
+
+  assert!(out.status.success(), "{text}");
+  assert!(!text.contains("Sign in"), "{text}");
+  // Each title stays with its own link, though the first result has none
+  let lines: Vec<&str> = text.lines().collect();
+  let title = lines
+    .iter()
+    .position(|l| l.contains("Linkin Park - Numb Lyrics"))
+    .expect("title listed");
+  assert!(
+    lines[title + 1].contains("/lyrics/Linkin-Park/Numb\x1b"),
+    "{text}"
+  );
+  assert!(text.contains("I'm tired of being what you want me to be"));
+}
+
+#[test]
+fn google_selectors_come_from_the_config() {
+  let server = FixtureServer::start(vec![
+    Route::ok("/search", "google_custom.html"),
+    Route::ok(TRACK, "mxm_track.html"),
+  ]);
+  let config = fixture_path("config_selectors.json");
+  let out = server.run_env(
+    &["-a", "--providers", "musixmatch", "-l", "numb"],
+    &[("LYRICS_CONFIG", config.to_str().unwrap())],
+  );
+  let text = stdout(&out);
+
+  assert!(
+    text.contains("Linkin Park - Numb Lyrics | Musixmatch"),
+    "{text}"
+  );
+  assert!(!text.contains("Open"), "{text}");
+
+  let config = fixture_path("config_bad_selector.json");
+  let out = server.run_env(&["numb"], &[("LYRICS_CONFIG", config.to_str().unwrap())]);
+  let err = String::from_utf8_lossy(&out.stderr);
+  assert!(!out.status.success());
+  assert!(err.contains("Invalid Google link selector"), "{err}");
+}
+
+#[test]
+fn google_consent_is_given_once() {
+  let server = FixtureServer::start(vec![
+    Route::ok("/search", "google_results.html").fail(302, "Location: /consent?continue=/search"),
+    Route::ok("/consent", "google_consent.html"),
+    Route::ok("/save", "google_consent_saved.html").header("Set-Cookie: SOCS=CAESEwgDEgk; Max-Age=3600; Path=/"),
+    Route::ok(TRACK, "mxm_track.html"),
+  ]);
+  let out = server.run(&["--providers", "musixmatch", "-l", "numb"]);
+
+  assert!(out.status.success(), "{}", stdout(&out));
+  {
+    let requests = server.requests.lock().unwrap();
+    let save: Vec<_> = requests.iter().filter(|r| r.target == "/save").collect();
+    assert_eq!(save.len(), 1);
+    assert_eq!(save[0].method, "POST");
+    // "Reject all" is the one sent
+    assert!(save[0].body.contains("set_eom=true"), "{}", save[0].body);
+  }
+  let cookies = server.cookies_sent("/search");
+  assert!(cookies[1].as_deref().is_some_and(|c| c.contains("SOCS=")));
+  let jar = std::fs::read_to_string(&server.cookie_jar).expect("cookie jar written");
+  assert!(jar.contains("SOCS"));
+
+  // The next run has the cookie already
+  let out = server.run(&["--no-cache", "--providers", "musixmatch", "-l", "numb"]);
+  assert!(out.status.success());
+  assert_eq!(server.request_count("/consent"), 1);
+}
+
+#[test]
+fn google_sorry_page_falls_back_to_the_next_engine() {
+  let server = FixtureServer::start(vec![
+    Route::ok("/search", "mxm_search.html").fail(302, "Location: /sorry/index?continue=/search"),
+    Route {
+      status: 429,
+      ..Route::ok("/sorry/index", "google_sorry.html")
+    },
+    Route::ok(TRACK, "mxm_track.html"),
+  ]);
+  let out = server.run(&["--providers", "musixmatch", "-l", "numb"]);
+  let err = String::from_utf8_lossy(&out.stderr);
+
+  assert!(out.status.success(), "{err}");
+  assert!(err.contains("Blocked for unusual traffic"), "{err}");
+  assert_eq!(server.request_count("/sorry/index"), 1);
+  assert!(server.requested(TRACK));
+}
+
+#[test]
+fn blocked_search_is_not_retried_when_other_engines_find_nothing() {
+  let server = FixtureServer::start(vec![
+    Route::ok("/search", "no_results.html").fail(302, "Location: /sorry/index?continue=/search"),
+    Route {
+      status: 429,
+      ..Route::ok("/sorry/index", "google_sorry.html")
+    },
+    Route::ok("/html", "no_results.html"),
+  ]);
+  let out = server.run(&["--providers", "musixmatch", "-T", "3", "numb"]);
+  let err = String::from_utf8_lossy(&out.stderr);
+
+  assert!(!out.status.success());
+  let fatal = err.lines().last().unwrap_or_default();
+  assert!(fatal.contains("Blocked for unusual traffic"), "{err}");
+  // Google, Musixmatch and Bing, once
+  assert_eq!(server.request_count("/search"), 3);
+}
+
+#[test]
+fn duckduckgo_redirect_links_are_decoded() {
+  let server = FixtureServer::start(vec![
+    Route::ok("/html", "duckduckgo_results.html"),
+    Route::ok(TRACK, "mxm_track.html"),
+  ]);
+  let out = server.run(&[
+    "--search-engine",
+    "duckduckgo",
+    "--providers",
+    "musixmatch",
+    "-l",
+    "numb",
+  ]);
+
+  assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+  assert!(!server.requested("/search"));
+  assert!(server.requested(TRACK));
+  assert!(stdout(&out).contains("I've become so numb"));
+  let requests = server.requests.lock().unwrap();
+  let search = requests.iter().find(|r| r.target.starts_with("/html")).unwrap();
+  assert!(
+    search.target.contains("site%3Amusixmatch.com%2Flyrics+numb"),
+    "{}",
+    search.target
+  );
+}
+
+#[test]
+fn bing_results_are_read() {
+  let server = FixtureServer::start(vec![
+    Route::ok("/search", "bing_results.html"),
+    Route::ok(TRACK, "mxm_track.html"),
+  ]);
+  let out = server.run(&[
+    "--search-engine",
+    "bing",
+    "--providers",
+    "musixmatch",
+    "-l",
+    "numb",
+  ]);
+
+  assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+  // Bing found it, no other engine is asked
+  assert_eq!(server.request_count("/search"), 1);
+  assert!(server.requested(TRACK));
+  let requests = server.requests.lock().unwrap();
+  assert!(
+    requests[0].target.contains("numb+lyrics"),
+    "{}",
+    requests[0].target
+  );
+}
+
+#[test]
+fn duckduckgo_and_bing_captchas_fall_back() {
+  let server = FixtureServer::start(vec![
+    Route::ok("/html", "duckduckgo_captcha.html"),
+    Route::ok("/search", "google_results.html"),
+    Route::ok(TRACK, "mxm_track.html"),
+  ]);
+  let out = server.run(&[
+    "--search-engine",
+    "duckduckgo",
+    "--providers",
+    "musixmatch",
+    "-l",
+    "numb",
+  ]);
+  let err = String::from_utf8_lossy(&out.stderr);
+
+  assert!(out.status.success(), "{err}");
+  assert!(err.contains("duckduckgo search failed: Captcha"), "{err}");
+  assert!(server.requested(TRACK));
+
+  // Every engine on /search gets the Bing challenge, DuckDuckGo does not
+  let server = FixtureServer::start(vec![
+    Route::ok("/search", "bing_captcha.html"),
+    Route::ok("/html", "duckduckgo_results.html"),
+    Route::ok(TRACK, "mxm_track.html"),
+  ]);
+  let out = server.run(&[
+    "--search-engine",
+    "bing",
+    "--providers",
+    "musixmatch",
+    "-l",
+    "numb",
+  ]);
+  let err = String::from_utf8_lossy(&out.stderr);
+
+  assert!(out.status.success(), "{err}");
+  assert!(err.contains("bing search failed: Captcha"), "{err}");
+  assert!(server.requested("/html/"));
+  assert!(server.requested(TRACK));
+}
+
+#[test]
+fn google_consent_is_not_sent_to_other_sites() {
+  let server = FixtureServer::start(vec![
+    Route::ok("/search", "google_results.html").fail(302, "Location: /consent?continue=/search"),
+    Route::ok("/consent", "google_consent_foreign.html"),
+  ]);
+  // Through the server as a proxy too, so a request to the foreign site is seen
+  let out = server.run(&["-p", &server.base, "--providers", "musixmatch", "-l", "numb"]);
+  let err = String::from_utf8_lossy(&out.stderr);
+
+  assert!(!out.status.success());
+  assert!(err.contains("Cookie consent asked"), "{err}");
+  assert!(server.requested("/consent"));
+  assert!(!server.requested("/save"));
+  assert!(
+    server.proxied_hosts().iter().all(|h| !h.contains("evil")),
+    "{:?}",
+    server.proxied_hosts()
+  );
+}
+
+#[test]
+fn failing_engines_fall_back_to_the_next_one() {
+  // Google is blocked, the Musixmatch search refuses scrapers
+  let server = FixtureServer::start(vec![
+    Route::ok("/search", "no_results.html")
+      .fail(302, "Location: /sorry/index?continue=/search")
+      .fail(403, ""),
+    Route {
+      status: 429,
+      ..Route::ok("/sorry/index", "google_sorry.html")
+    },
+    Route::ok("/html", "duckduckgo_results.html"),
+    Route::ok(TRACK, "mxm_track.html"),
+  ]);
+  let out = server.run(&["--providers", "musixmatch", "-l", "numb"]);
+  let err = String::from_utf8_lossy(&out.stderr);
+
+  assert!(out.status.success(), "{err}");
+  assert!(err.contains("musixmatch search failed"), "{err}");
+  assert!(server.requested("/html/"));
+  assert!(server.requested(TRACK));
+}
+
+#[test]
+fn google_interstitial_is_reported() {
+  let server = FixtureServer::start(vec![Route::ok("/search", "google_interstitial.html")]);
+  let out = server.run(&["--providers", "musixmatch", "numb"]);
+  let err = String::from_utf8_lossy(&out.stderr);
+
+  assert!(!out.status.success());
+  assert!(err.contains("Interstitial page instead of results"), "{err}");
+  assert!(!server.requested("/save"));
+}
+
+#[test]
+fn blocked_search_engine_fails() {
+  let server = FixtureServer::start(vec![Route {
+    status: 429,
+    ..Route::ok("/search", "google_results.html")
+  }]);
+  let out = server.run(&["--providers", "musixmatch", "numb"]);
+
+  assert!(!out.status.success());
+  assert!(!server.requested(TRACK));
+}
+
+#[test]
+fn cookies_survive_between_runs() {
+  let server = FixtureServer::start(vec![
+    Route::ok("/search", "google_results.html").header("Set-Cookie: NID=511=fixture; Max-Age=3600; Path=/"),
+    Route::ok(TRACK, "mxm_track.html").header("Set-Cookie: mxm-session=1; Path=/"),
+  ]);
+
+  assert!(server.run(&["--no-cache", "-l", "numb"]).status.success());
+  assert!(server.cookie_jar.is_file());
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = std::fs::metadata(&server.cookie_jar)
+      .unwrap()
+      .permissions()
+      .mode();
+    assert_eq!(mode & 0o777, 0o600);
+  }
+  assert!(server.run(&["--no-cache", "-l", "numb"]).status.success());
+
+  let sent = server.cookies_sent("/search");
+  assert_eq!(sent[0], None);
+  let second = sent[1].as_deref().unwrap_or_default();
+  assert!(second.contains("NID=511=fixture"), "{second}");
+  // Session cookies are kept too
+  assert!(second.contains("mxm-session=1"), "{second}");
+}
+
+#[test]
+fn google_cookies_option_only_goes_to_google() {
+  let server = FixtureServer::start(vec![
+    Route::ok("/search", "google_results.html").header("Set-Cookie: NID=511=fixture; Max-Age=3600; Path=/"),
+    Route::ok(TRACK, "mxm_track.html"),
+  ]);
+  assert!(server.run(&["--no-cache", "-l", "numb"]).status.success());
+
+  // Another host for Musixmatch, cookies are kept per host
+  let mxm = server.base.replace("127.0.0.1", "localhost");
+  let out = server.run_env(
+    &["--no-cache", "-C", "SID=given; HSID=too", "-l", "numb"],
+    &[("LYRICS_MUSIXMATCH_URL", &mxm)],
+  );
+  assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+
+  let search = server.cookies_sent("/search")[1].clone().unwrap_or_default();
+  // Given along with the jar ones, not instead of them
+  assert!(search.contains("SID=given"), "{search}");
+  assert!(search.contains("HSID=too"), "{search}");
+  assert!(search.contains("NID=511=fixture"), "{search}");
+  let track = server.cookies_sent(TRACK)[1].clone().unwrap_or_default();
+  assert!(!track.contains("SID="), "{track}");
+}
+
+#[test]
+fn server_errors_are_retried_with_backoff() {
+  let server = FixtureServer::start(vec![
+    Route::ok("/search", "google_results.html")
+      .fail(503, "")
+      .fail(500, ""),
+    Route::ok(TRACK, "mxm_track.html"),
+  ]);
+  let out = server.run(&["-T", "3", "-l", "numb"]);
+
+  assert!(out.status.success(), "{}", stdout(&out));
+  // The first try asks Google, Musixmatch (both failing) and Bing (nothing
+  // found), the second one gets the Google results
+  assert_eq!(server.request_count("/search"), 4);
+}
+
+#[test]
+fn rate_limits_wait_for_retry_after() {
+  let server = FixtureServer::start(vec![
+    Route::ok("/search", "google_results.html"),
+    Route::ok(TRACK, "mxm_track.html").fail(429, "Retry-After: 1"),
+  ]);
+  let start = std::time::Instant::now();
+  let out = server.run(&["-T", "2", "-l", "numb"]);
+
+  assert!(out.status.success(), "{}", stdout(&out));
+  assert_eq!(server.request_count(TRACK), 2);
+  assert!(start.elapsed() >= std::time::Duration::from_secs(1));
+}
+
+#[test]
+fn long_retry_after_fails_at_once() {
+  let server = FixtureServer::start(vec![
+    Route::ok("/search", "google_results.html"),
+    Route::ok(TRACK, "mxm_track.html").fail(429, "Retry-After: 3600"),
+  ]);
+  let start = std::time::Instant::now();
+  let out = server.run(&["-T", "2", "--providers", "musixmatch", "-l", "numb"]);
+  let err = String::from_utf8_lossy(&out.stderr);
+
+  assert!(!out.status.success());
+  assert!(err.contains("asked to wait 3600s"), "{err}");
+  assert_eq!(server.request_count(TRACK), 1);
+  assert!(start.elapsed() < std::time::Duration::from_secs(3));
+}
+
+#[test]
+fn client_errors_are_not_retried() {
+  let server = FixtureServer::start(vec![Route::ok("/search", "google_results.html")]);
+  let out = server.run(&["-T", "4", "--providers", "musixmatch", "numb"]);
+
+  assert!(!out.status.success());
+  assert_eq!(server.request_count(TRACK), 1);
+}
+
+#[test]
+fn deadline_stops_retries() {
+  let server = FixtureServer::start(vec![Route {
+    status: 500,
+    ..Route::ok("/search", "google_results.html")
+  }]);
+  let start = std::time::Instant::now();
+  let out = server.run(&[
+    "-T",
+    "20",
+    "--backoff",
+    "200",
+    "--deadline",
+    "1000",
+    "--providers",
+    "musixmatch",
+    "numb",
+  ]);
+
+  assert!(!out.status.success());
+  // DuckDuckGo is asked once per try
+  assert!(server.request_count("/html/") < 20);
+  assert!(start.elapsed() < std::time::Duration::from_secs(3));
+}
+
+#[test]
+fn requests_go_through_the_proxy_list() {
+  let server = musixmatch_site();
+  let list = std::env::temp_dir().join(format!("lyrics-test-proxies-{}.txt", std::process::id()));
+  // The first proxy refuses connections, so it is skipped
+  std::fs::write(
+    &list,
+    format!("# Test proxies\n\nhttp://127.0.0.1:9\n{}\n", server.base),
+  )
+  .unwrap();
+
+  // Only reachable through the proxy
+  let site = "http://lyrics.invalid";
+  let env: Vec<(&str, &str)> = ["LYRICS_GOOGLE_URL", "LYRICS_MUSIXMATCH_URL"]
+    .iter()
+    .map(|k| (*k, site))
+    .collect();
+  let out = server.run_env(
+    &[
+      "-T",
+      "2",
+      "-P",
+      list.to_str().unwrap(),
+      "--providers",
+      "musixmatch",
+      "-l",
+      "numb",
+    ],
+    &env,
+  );
+  std::fs::remove_file(&list).unwrap();
+
+  assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+  assert!(server.requested("/search"));
+  assert!(server.requested(TRACK));
+  assert!(server.proxied_hosts().iter().all(|h| h == "lyrics.invalid"));
+  assert!(stdout(&out).contains("I've become so numb"));
+}
+
+#[test]
+fn other_providers_go_through_the_proxy_list() {
+  let server = FixtureServer::start(vec![
+    Route::ok("/embed/track/2nLtzopw4rPReszdYBJU6h", "spotify_embed.html"),
+    Route::ok("/api/search", "lrclib_search.json"),
+    Route::ok("/api/get/101", "lrclib_get.json"),
+  ]);
+  let list = std::env::temp_dir().join(format!(
+    "lyrics-test-provider-proxies-{}.txt",
+    std::process::id()
+  ));
+  // The first proxy refuses connections, so it is skipped
+  std::fs::write(&list, format!("http://127.0.0.1:9\n{}\n", server.base)).unwrap();
+
+  let site = "http://lyrics.invalid";
+  let env: Vec<(&str, &str)> = ["LYRICS_SPOTIFY_URL", "LYRICS_LRCLIB_URL"]
+    .iter()
+    .map(|k| (*k, site))
+    .collect();
+  let out = server.run_env(
+    &[
+      "-P",
+      list.to_str().unwrap(),
+      "--providers",
+      "lrclib",
+      "-l",
+      "spotify:track:2nLtzopw4rPReszdYBJU6h",
+    ],
+    &env,
+  );
+  std::fs::remove_file(&list).unwrap();
+
+  assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+  assert!(server.requested("/embed/track/2nLtzopw4rPReszdYBJU6h"));
+  assert!(server.requested("/api/get/101"));
+  assert!(server.proxied_hosts().iter().all(|h| h == "lyrics.invalid"));
+  assert!(stdout(&out).contains("I've become so numb"));
+}
+
+#[test]
+fn google_captcha_moves_to_the_next_proxy() {
+  // The first proxy only gets captchas, the second one gets through
+  let flagged = FixtureServer::start(vec![Route::ok("/search", "google_sorry.html")]);
+  let server = musixmatch_site();
+  let list = std::env::temp_dir().join(format!(
+    "lyrics-test-captcha-proxies-{}.txt",
+    std::process::id()
+  ));
+  std::fs::write(&list, format!("{}\n{}\n", flagged.base, server.base)).unwrap();
+
+  let site = "http://lyrics.invalid";
+  let env: Vec<(&str, &str)> = ["LYRICS_GOOGLE_URL", "LYRICS_MUSIXMATCH_URL"]
+    .iter()
+    .map(|k| (*k, site))
+    .collect();
+  let out = flagged.run_env(
+    &[
+      "-T",
+      "2",
+      "-P",
+      list.to_str().unwrap(),
+      "--providers",
+      "musixmatch",
+      "-l",
+      "numb",
+    ],
+    &env,
+  );
+  std::fs::remove_file(&list).unwrap();
+
+  assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+  assert!(flagged.requested("/search"));
+  assert!(!flagged.requested(TRACK));
+  assert!(server.requested("/search"));
+  assert!(server.requested(TRACK));
+  assert!(stdout(&out).contains("I've become so numb"));
+}
+
+#[test]
+fn invalid_proxy_is_reported() {
+  let server = musixmatch_site();
+  let out = server.run(&["-p", "ftp://[::1", "numb"]);
+
+  assert!(!out.status.success());
+  assert!(String::from_utf8_lossy(&out.stderr).contains("Invalid proxy"));
+  assert!(server.requests.lock().unwrap().is_empty());
+}
+
+#[test]
+fn second_run_comes_from_the_cache() {
+  let server = musixmatch_site();
+  server.run(&["numb"]);
+  let second = server.run(&["numb"]);
+
+  assert!(second.status.success());
+  assert!(stdout(&second).contains("Copyright -> Writer(s): Chester Bennington, Mike Shinoda"));
+  assert_eq!(server.request_count("/search"), 1);
+  assert_eq!(server.request_count(TRACK), 1);
+
+  // Same page, other URL form
+  let out = server.run(&["-l", "https://musixmatch.com/lyrics/Linkin-Park/Numb/"]);
+  assert!(out.status.success());
+  assert_eq!(server.request_count(TRACK), 1);
+}
+
+#[test]
+fn refresh_and_no_cache_send_requests() {
+  let server = musixmatch_site();
+  server.run(&["numb"]);
+  server.run(&["--refresh", "numb"]);
+  server.run(&["--no-cache", "numb"]);
+  assert_eq!(server.request_count(TRACK), 3);
+
+  // The refresh stored it again, the no-cache run did not touch it
+  server.run(&["numb"]);
+  assert_eq!(server.request_count(TRACK), 3);
+}
+
+#[test]
+fn offline_uses_only_the_cache() {
+  let server = musixmatch_site();
+  let out = server.run(&["--offline", "numb"]);
+  assert!(!out.status.success());
+  assert!(server.requests.lock().unwrap().is_empty());
+
+  server.run(&["numb"]);
+  let requests = server.requests.lock().unwrap().len();
+  // Expired entries are still used offline
+  let out = server.run(&["--offline", "--cache-ttl", "0", "-l", "numb"]);
+  assert!(out.status.success());
+  assert!(stdout(&out).contains("I'm tired of being what you want me to be"));
+  assert_eq!(server.requests.lock().unwrap().len(), requests);
+}
+
+#[test]
+fn cache_subcommand_lists_prunes_and_clears() {
+  let server = musixmatch_site();
+  server.run(&["numb"]);
+
+  let list = stdout(&server.run(&["cache", "list"]));
+  assert!(list.contains("search"));
+  assert!(list.contains("numb"));
+  assert!(list.contains("/lyrics/linkin-park/numb"));
+
+  let out = server.run(&["cache", "prune"]);
+  assert!(String::from_utf8_lossy(&out.stderr).contains("Removed 0 expired entries"));
+  let out = server.run(&["cache", "prune", "--cache-ttl", "0"]);
+  assert!(String::from_utf8_lossy(&out.stderr).contains("Removed 2 expired entries"));
+
+  server.run(&["numb"]);
+  let out = server.run(&["cache", "clear"]);
+  assert!(String::from_utf8_lossy(&out.stderr).contains("Removed 2 entries"));
+  assert!(stdout(&server.run(&["cache", "list"])).is_empty());
+}
+
+#[test]
+fn queries_starting_with_command_words_are_searched() {
+  for args in [
+    vec!["--providers", "musixmatch", "-l", "album", "of", "the", "year"],
+    vec!["-l", "cache", "me", "outside", "now"],
+    vec!["--", "artist", "of", "the", "year"],
+  ] {
+    let server = musixmatch_site();
+    let out = server.run(&args);
+
+    assert!(
+      out.status.success(),
+      "{args:?}: {}",
+      String::from_utf8_lossy(&out.stderr)
+    );
+    assert!(!server.requested(ALBUM));
+    let query = args[args.len() - 4..].join("+");
+    let requests = server.requests.lock().unwrap();
+    let search = requests
+      .iter()
+      .find(|r| r.target.starts_with("/search"))
+      .unwrap();
+    assert!(search.target.contains(&query), "{}", search.target);
+  }
+}
+
+#[test]
+fn album_lyrics_in_tracklist_order() {
+  let server = musixmatch_site();
+  let out = server.run(&["album", "https://www.musixmatch.com/album/Linkin-Park/Meteora"]);
+  let text = stdout(&out);
+
+  assert!(out.status.success(), "{text}");
+  assert!(text.contains("Meteora - Linkin Park (2003-03-25)"));
+  let order: Vec<usize> = [
+    "1. Foreword",
+    "2. Numb",
+    "I've become so numb",
+    "3. Breaking the Habit",
+  ]
+  .iter()
+  .map(|s| text.find(s).unwrap_or_else(|| panic!("{s} missing: {text}")))
+  .collect();
+  assert!(order.windows(2).all(|w| w[0] < w[1]));
+  assert!(server.requested("/lyrics/Linkin-Park/Foreword"));
+  assert!(String::from_utf8_lossy(&out.stderr).contains("Could not get 'Breaking the Habit'"));
+}
+
+#[test]
+fn album_of_a_searched_song() {
+  let server = musixmatch_site();
+  let out = server.run(&["album", "numb"]);
+
+  assert!(out.status.success(), "{}", stdout(&out));
+  assert!(server.requested("/search"));
+  assert!(server.requested(ALBUM));
+  // The song is in the album too, it comes from the cache then
+  assert_eq!(server.request_count(TRACK), 1);
+}
+
+#[test]
+fn album_as_one_file_per_song() {
+  let server = musixmatch_site();
+  let dir = server.cache_dir.with_extension("album");
+  let out = server.run(&[
+    "album",
+    "-o",
+    dir.to_str().unwrap(),
+    "www.musixmatch.com/album/Linkin-Park/Meteora",
+  ]);
+
+  assert!(out.status.success(), "{}", stdout(&out));
+  let files: Vec<String> = std::fs::read_dir(&dir)
+    .unwrap()
+    .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+    .collect();
+  let numb = std::fs::read_to_string(dir.join("02 - Numb.txt")).unwrap_or_default();
+  let _ = std::fs::remove_dir_all(&dir);
+
+  assert_eq!(files, ["02 - Numb.txt"]);
+  assert!(numb.starts_with("I'm tired of being what you want me to be"));
+}
+
+#[test]
+fn artist_albums_across_pages() {
+  let server = musixmatch_site();
+  let out = server.run(&[
+    "artist",
+    "--delay",
+    "0",
+    "--albums-only",
+    "https://www.musixmatch.com/artist/Linkin-Park",
+  ]);
+  let text = stdout(&out);
+
+  assert!(out.status.success(), "{text}");
+  assert!(text.contains("Linkin Park\nhttps://www.musixmatch.com/artist/Linkin-Park"));
+  assert!(text.contains("Meteora (2003-03-25)"));
+  assert!(text.contains("Hybrid Theory (2000-10-24)"));
+  assert!(server.requested("/artist/Linkin-Park/albums/2"));
+  assert!(!server.requested(ALBUM));
+}
+
+#[test]
+fn artist_albums_cached_per_page_limit() {
+  let server = musixmatch_site();
+  let artist = "https://www.musixmatch.com/artist/Linkin-Park";
+  let run = |pages: &str| {
+    let out = server.run(&[
+      "artist",
+      "--delay",
+      "0",
+      "--albums-only",
+      "--max-pages",
+      pages,
+      artist,
+    ]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    stdout(&out)
+  };
+
+  assert!(!run("1").contains("Hybrid Theory"));
+  assert!(!server.requested("/artist/Linkin-Park/albums/2"));
+  // The one page list is not taken for the whole discography
+  assert!(run("20").contains("Hybrid Theory (2000-10-24)"));
+  assert!(server.requested("/artist/Linkin-Park/albums/2"));
+  // Each list is cached on its own
+  run("1");
+  run("20");
+  assert_eq!(server.request_count("/artist/Linkin-Park/albums"), 2);
+}
+
+#[test]
+fn artist_of_a_searched_song_with_songs() {
+  let server = musixmatch_site();
+  let out = server.run(&["artist", "--delay", "0", "numb"]);
+  let text = stdout(&out);
+
+  assert!(out.status.success(), "{text}");
+  let order: Vec<usize> = [
+    "Meteora (2003-03-25)",
+    "  1. Foreword\n",
+    "  2. Numb\n",
+    "  3. Breaking the Habit\n",
+    "Hybrid Theory",
+  ]
+  .iter()
+  .map(|s| text.find(s).unwrap_or_else(|| panic!("{s} missing: {text}")))
+  .collect();
+  assert!(order.windows(2).all(|w| w[0] < w[1]));
+  assert!(String::from_utf8_lossy(&out.stderr).contains("Could not get the songs of 'Hybrid Theory'"));
+  // Listing does not get the songs
+  assert!(!server.requested(TRACK));
+}
+
+#[test]
+fn artist_prefetch_fills_the_cache() {
+  let server = musixmatch_site();
+  let out = server.run(&[
+    "artist",
+    "--delay",
+    "0",
+    "--prefetch",
+    "musixmatch.com/album/Linkin-Park/Meteora",
+  ]);
+
+  assert!(out.status.success(), "{}", stdout(&out));
+  assert!(String::from_utf8_lossy(&out.stderr).contains("Cached 1 songs with lyrics and 0 without, 2 failed"));
+  assert_eq!(server.request_count(TRACK), 1);
+
+  let requests = server.requests.lock().unwrap().len();
+  let out = server.run(&[
+    "--offline",
+    "-l",
+    "https://www.musixmatch.com/lyrics/Linkin-Park/Numb",
+  ]);
+  assert!(out.status.success());
+  assert_eq!(server.requests.lock().unwrap().len(), requests);
+
+  let out = server.run(&["artist", "--no-cache", "--prefetch", "numb"]);
+  assert!(!out.status.success());
+}
+
+#[test]
+fn artist_requests_are_spaced() {
+  let server = musixmatch_site();
+  let start = Instant::now();
+  let out = server.run(&[
+    "artist",
+    "--delay",
+    "100",
+    "--prefetch",
+    "https://www.musixmatch.com/artist/Linkin-Park",
+  ]);
+
+  assert!(out.status.success(), "{}", stdout(&out));
+  // Two artist pages, two album pages and three songs
+  assert_eq!(server.requests.lock().unwrap().len(), 7);
+  assert!(start.elapsed() >= Duration::from_millis(600));
+}